@@ -1,6 +1,7 @@
 use self::on_hit::{OnHit, SplashEffect};
 use crate::{
-    creeps::{self, Death, Life},
+    creeps::{self, Armor, DamageDealt, DamageSource, Death, Life},
+    damage::{ArmorType, DamageType},
     towers::{Damage, Range},
 };
 use bevy::prelude::{self, *};
@@ -12,9 +13,10 @@ pub struct Plugin;
 
 impl prelude::Plugin for Plugin {
     fn build(&self, app: &mut App) {
+        // Towers removed by commands during the update are only seen as removed by later stages
         app.add_system(splash)
-            .add_system(aura::Auras::apply_aura_to_new_tower)
-            .add_system(aura::Auras::apply_new_aura);
+            .add_system(aura::Auras::debuff_creeps)
+            .add_system_to_stage(CoreStage::PostUpdate, aura::Auras::recompute);
     }
 }
 
@@ -29,14 +31,29 @@ pub struct OnHitAbilities(pub Vec<OnHit>);
 )]
 fn splash(
     mut commands: Commands,
-    mut ew: EventWriter<Death>,
-    splash: Query<(Entity, &GlobalTransform, &SplashEffect, &Range, &Damage)>,
-    mut creeps: Query<(Entity, &mut Life, &GlobalTransform), With<creeps::Type>>,
+    mut deaths: EventWriter<Death>,
+    mut dealt: EventWriter<DamageDealt>,
+    splash: Query<(
+        Entity,
+        &GlobalTransform,
+        &SplashEffect,
+        &Range,
+        &Damage,
+        &DamageType,
+        &DamageSource,
+    )>,
+    mut creeps: Query<
+        (Entity, &mut Life, &Armor, &ArmorType, &GlobalTransform),
+        With<creeps::Type>,
+    >,
 ) {
-    for (entity, transform, effect, range, damage) in splash.iter() {
-        for (entity, mut life, creep_position) in creeps.iter_mut() {
+    for (entity, transform, effect, range, damage, damage_type, DamageSource(source)) in
+        splash.iter()
+    {
+        for (entity, mut life, armor, armor_type, creep_position) in creeps.iter_mut() {
             if range.within(creep_position.translation, transform.translation) {
                 creeps::damage_creep(
+                    *source,
                     entity,
                     match effect {
                         SplashEffect::Multiplier { multiplier } => {
@@ -49,8 +66,12 @@ fn splash(
                                 * *multiplier) as u64
                         }
                     },
+                    *damage_type,
+                    armor,
+                    *armor_type,
                     &mut life,
-                    &mut ew,
+                    &mut deaths,
+                    &mut dealt,
                 );
             }
         }
@@ -1,72 +1,252 @@
 use crate::{
-    buffs::{self, AttackSpeed},
-    towers::{get_all_towers_within_range, Range, Tower},
+    buffs::{Buffs, Debuffs},
+    creeps,
+    towers::{Range, Tower},
 };
 use bevy::prelude::*;
+use serde::Deserialize;
+use std::fmt;
 
 #[derive(Component)]
 pub struct Auras(pub Vec<Aura>);
 
+#[derive(Clone, Deserialize)]
 pub struct Aura {
     pub range: Range,
     pub kind: Kind,
 }
 
+#[derive(Clone, Deserialize)]
 pub enum Kind {
     AttackSpeed(f32),
+    /// Percentage added to the damage of towers
+    Damage(u32),
+    Range(f32),
+    /// Percent chance of towers dealing double damage
+    CritChance(u32),
+    /// Percentage added to the chance of on-hit abilities of towers
+    OnHitChance(u32),
+    /// Armor removed from creeps
+    ArmorReduction(u32),
+    /// Percentage the speed of creeps is reduced by
+    Slow(u32),
+}
+
+impl Kind {
+    /// Raises the matching buff to the aura's value if it is stronger, creep auras are ignored
+    fn buff(&self, buffs: &mut Buffs) {
+        match *self {
+            Kind::AttackSpeed(val) => buffs.attack_speed = buffs.attack_speed.max(val),
+            Kind::Damage(val) => buffs.damage = buffs.damage.max(val),
+            Kind::Range(val) => buffs.range = buffs.range.max(val),
+            Kind::CritChance(val) => buffs.crit_chance = buffs.crit_chance.max(val),
+            Kind::OnHitChance(val) => buffs.on_hit_chance = buffs.on_hit_chance.max(val),
+            Kind::ArmorReduction(_) | Kind::Slow(_) => {}
+        }
+    }
+
+    /// Raises the matching debuff to the aura's value if it is stronger, tower auras are ignored
+    fn debuff(&self, debuffs: &mut Debuffs) {
+        match *self {
+            Kind::ArmorReduction(val) => debuffs.armor = debuffs.armor.max(val),
+            Kind::Slow(val) => debuffs.slow = debuffs.slow.max(val),
+            Kind::AttackSpeed(_)
+            | Kind::Damage(_)
+            | Kind::Range(_)
+            | Kind::CritChance(_)
+            | Kind::OnHitChance(_) => {}
+        }
+    }
+}
+
+impl fmt::Display for Aura {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            Kind::AttackSpeed(val) => write!(f, "+{} attack speed", val),
+            Kind::Damage(val) => write!(f, "+{}% damage", val),
+            Kind::Range(val) => write!(f, "+{} range", val),
+            Kind::CritChance(val) => write!(f, "{}% chance of critical hits", val),
+            Kind::OnHitChance(val) => write!(f, "+{}% on-hit ability chance", val),
+            Kind::ArmorReduction(val) => write!(f, "-{} armor to creeps", val),
+            Kind::Slow(val) => write!(f, "Slow creeps by {}", val),
+        }?;
+        write!(f, " in {} range", self.range.0)
+    }
 }
 
 impl Auras {
-    pub fn apply_new_aura(
+    /// Recomputes the buffs of every tower from scratch whenever a tower is added, removed, moved or has its auras
+    /// changed. Only the strongest aura of each kind reaching a tower applies
+    pub fn recompute(
         mut commands: Commands,
-        auras: Query<(&Transform, &Auras), Added<Auras>>,
-        towers: Query<(Entity, &GlobalTransform, Option<&buffs::AttackSpeed>), With<Tower>>,
+        changed: Query<
+            (),
+            (
+                With<Tower>,
+                Or<(Added<Tower>, Changed<Auras>, Changed<GlobalTransform>)>,
+            ),
+        >,
+        removed: RemovedComponents<Tower>,
+        auras: Query<(&GlobalTransform, &Auras), With<Tower>>,
+        towers: Query<(Entity, &GlobalTransform, Option<&Buffs>), With<Tower>>,
     ) {
-        for (transform, Auras(auras)) in auras.iter() {
-            let position = transform.translation;
-            for Aura { range, kind } in auras {
-                for tower in get_all_towers_within_range(&towers, &position, *range) {
-                    match kind {
-                        Kind::AttackSpeed(val) => {
-                            if let Ok(buffs::AttackSpeed(existing_buff)) =
-                                towers.get_component::<AttackSpeed>(tower)
-                            {
-                                if existing_buff < val {
-                                    commands.entity(tower).insert(buffs::AttackSpeed(*val));
-                                }
-                            } else {
-                                commands.entity(tower).insert(buffs::AttackSpeed(*val));
-                            }
-                        }
-                    }
+        if changed.is_empty() && removed.iter().next().is_none() {
+            return;
+        }
+
+        for (tower, position, current) in towers.iter() {
+            let mut buffs = Buffs::default();
+            for Aura { kind, .. } in reaching(auras.iter(), position.translation) {
+                kind.buff(&mut buffs);
+            }
+
+            match current {
+                Some(current) if *current == buffs => {}
+                _ if buffs != Buffs::default() => {
+                    commands.entity(tower).insert(buffs);
+                }
+                Some(_) => {
+                    commands.entity(tower).remove::<Buffs>();
                 }
+                None => {}
             }
         }
     }
 
-    pub fn apply_aura_to_new_tower(
-        mut commands: Commands,
-        auras: Query<(&Transform, &Auras)>,
-        towers: Query<(Entity, &GlobalTransform, Option<&buffs::AttackSpeed>), Added<Tower>>,
+    /// Creeps move every frame, so their debuffs are recomputed every frame. Only the strongest aura of each kind
+    /// reaching a creep applies
+    pub fn debuff_creeps(
+        auras: Query<(&GlobalTransform, &Auras), With<Tower>>,
+        mut creeps: Query<(&GlobalTransform, &mut Debuffs), With<creeps::Type>>,
     ) {
-        for (tower, tower_position, attack_speed) in towers.iter() {
-            for (aura_position, Auras(auras)) in auras.iter() {
-                for Aura { range, kind } in auras.iter() {
-                    if range.within(tower_position.translation, aura_position.translation) {
-                        match kind {
-                            Kind::AttackSpeed(val) => {
-                                if let Some(buffs::AttackSpeed(existing_buff)) = attack_speed {
-                                    if existing_buff < val {
-                                        commands.entity(tower).insert(buffs::AttackSpeed(*val));
-                                    }
-                                } else {
-                                    commands.entity(tower).insert(buffs::AttackSpeed(*val));
-                                }
-                            }
-                        }
-                    }
-                }
+        for (position, mut current) in creeps.iter_mut() {
+            let mut debuffs = Debuffs::default();
+            for Aura { kind, .. } in reaching(auras.iter(), position.translation) {
+                kind.debuff(&mut debuffs);
+            }
+            // Only written when different so slows are not recomputed every frame
+            if *current != debuffs {
+                *current = debuffs;
             }
         }
     }
 }
+
+/// Every aura whose range reaches the position
+fn reaching<'a>(
+    auras: impl Iterator<Item = (&'a GlobalTransform, &'a Auras)>,
+    position: Vec3,
+) -> impl Iterator<Item = &'a Aura> {
+    auras.flat_map(move |(aura_position, Auras(auras))| {
+        auras
+            .iter()
+            .filter(move |Aura { range, .. }| range.within(position, aura_position.translation))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        simulation::Simulation,
+        towers::{AttackSpeed, BaseAttackSpeed, Gem, GemQuality, GemType},
+    };
+
+    fn level_1() -> Simulation {
+        Simulation::load(
+            "assets/maps/level_1.map",
+            "assets/gems.towers",
+            "assets/level_1.waves",
+        )
+        .unwrap()
+    }
+
+    fn place(
+        simulation: &mut Simulation,
+        pos: (i32, i32),
+        quality: GemQuality,
+        r#type: GemType,
+    ) -> Entity {
+        simulation.place_gem(pos, Gem { quality, r#type }).unwrap()
+    }
+
+    /// Runs a few frames so transforms propagate, buffs are recomputed and then applied
+    fn settle(simulation: &mut Simulation) {
+        for _ in 0..3 {
+            simulation.update();
+        }
+    }
+
+    /// Asserts how much faster than its base speed the tower attacks
+    fn assert_bonus(simulation: &Simulation, tower: Entity, bonus: f32) {
+        let world = simulation.world();
+        let actual = world.get::<AttackSpeed>(tower).unwrap().0
+            - world.get::<BaseAttackSpeed>(tower).unwrap().0;
+        assert!((actual - bonus).abs() < 1e-4, "{} != {}", actual, bonus);
+    }
+
+    #[test]
+    fn aura_buffs_until_removed() {
+        let mut simulation = level_1();
+        let ruby = place(
+            &mut simulation,
+            (-10, 4),
+            GemQuality::Chipped,
+            GemType::Ruby,
+        );
+        let opal = place(&mut simulation, (-8, 4), GemQuality::Chipped, GemType::Opal);
+        settle(&mut simulation);
+        assert_bonus(&simulation, ruby, 0.1);
+
+        simulation.world_mut().despawn(opal);
+        settle(&mut simulation);
+        assert_bonus(&simulation, ruby, 0.0);
+        assert!(simulation.world().get::<Buffs>(ruby).is_none());
+    }
+
+    #[test]
+    fn strongest_overlapping_aura_applies() {
+        let mut simulation = level_1();
+        let ruby = place(
+            &mut simulation,
+            (-10, 4),
+            GemQuality::Chipped,
+            GemType::Ruby,
+        );
+        place(&mut simulation, (-8, 4), GemQuality::Chipped, GemType::Opal);
+        let perfect = place(
+            &mut simulation,
+            (-12, 4),
+            GemQuality::Perfect,
+            GemType::Opal,
+        );
+        settle(&mut simulation);
+        assert_bonus(&simulation, ruby, 0.35);
+
+        // The weaker aura takes over once the stronger one is gone
+        simulation.world_mut().despawn(perfect);
+        settle(&mut simulation);
+        assert_bonus(&simulation, ruby, 0.1);
+    }
+
+    #[test]
+    fn strongest_of_each_kind() {
+        let mut buffs = Buffs::default();
+        let mut debuffs = Debuffs::default();
+        for kind in &[
+            Kind::Damage(10),
+            Kind::Damage(25),
+            Kind::Damage(15),
+            Kind::CritChance(5),
+            Kind::Slow(20),
+        ] {
+            kind.buff(&mut buffs);
+            kind.debuff(&mut debuffs);
+        }
+        assert_eq!(buffs.damage, 25);
+        assert_eq!(buffs.crit_chance, 5);
+        assert!(buffs.attack_speed.abs() <= f32::EPSILON);
+        assert_eq!(debuffs.slow, 20);
+        assert_eq!(debuffs.armor, 0);
+    }
+}
@@ -1,9 +1,16 @@
 use bevy::prelude::*;
 use rand::Rng;
+use serde::Deserialize;
+use std::fmt;
 
-use crate::towers::{emerald, sapphire, Damage, Range};
+use crate::{
+    creeps::DamageSource,
+    damage::DamageType,
+    effects::{ApplyEffect, Effect, EffectKind, Stacking},
+    towers::{Damage, Range},
+};
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Deserialize)]
 pub enum OnHit {
     MultiplyDamage { chance: u32, multiplier: u64 },
     Splash(SplashEffect, Range),
@@ -11,17 +18,38 @@ pub enum OnHit {
     EmeraldPoison { dps: u32, slow: u32, duration: f32 },
 }
 
-#[derive(Clone, Component, Copy)]
+#[derive(Clone, Component, Copy, Deserialize)]
 pub enum SplashEffect {
     Multiplier { multiplier: f32 },
 }
 
 impl OnHit {
-    #[allow(clippy::cast_precision_loss)]
-    pub fn apply(self, target: Entity, commands: &mut Commands, damage: &mut u64, position: Vec3) {
+    /// The ability with `bonus` added to its chance of triggering, abilities without a chance are unchanged
+    pub fn with_bonus_chance(self, bonus: u32) -> Self {
+        match self {
+            OnHit::MultiplyDamage { chance, multiplier } => OnHit::MultiplyDamage {
+                chance: chance.saturating_add(bonus),
+                multiplier,
+            },
+            ability => ability,
+        }
+    }
+
+    /// Applies the ability of tower `source` hitting `target`
+    pub fn apply(
+        self,
+        source: Entity,
+        target: Entity,
+        commands: &mut Commands,
+        effects: &mut EventWriter<ApplyEffect>,
+        damage: &mut u64,
+        damage_type: DamageType,
+        position: Vec3,
+        rng: &mut impl Rng,
+    ) {
         match self {
             OnHit::MultiplyDamage { chance, multiplier } => {
-                if rand::thread_rng().gen_range(0..100) <= chance {
+                if rng.gen_range(0..100) <= chance {
                     *damage *= multiplier;
                 }
             }
@@ -30,26 +58,73 @@ impl OnHit {
                     effect,
                     range,
                     Damage::Fixed(*damage),
+                    damage_type,
+                    DamageSource(source),
                     Transform::from_translation(position),
                     GlobalTransform::default(),
                 ));
             }
-            OnHit::SapphireSlow(amount) => {
-                commands
-                    .entity(target)
-                    .insert(sapphire::Slowed(amount, Timer::from_seconds(4.0, false)));
-            }
+            OnHit::SapphireSlow(amount) => effects.send(ApplyEffect {
+                target,
+                effect: Effect::new(
+                    EffectKind::Slow,
+                    source,
+                    amount,
+                    4.0,
+                    Stacking::StrongestWins,
+                ),
+            }),
             OnHit::EmeraldPoison {
                 dps,
                 slow,
                 duration,
             } => {
-                commands.entity(target).insert(emerald::Poison {
-                    slow,
-                    duration_timer: Timer::from_seconds(duration, false),
-                    damage_timer: Timer::from_seconds(1.0 / (dps as f32), true),
+                effects.send(ApplyEffect {
+                    target,
+                    effect: Effect::new(
+                        EffectKind::Poison,
+                        source,
+                        dps,
+                        duration,
+                        Stacking::Refresh,
+                    )
+                    .with_tick(1.0)
+                    .with_damage_type(damage_type),
                 });
+                effects.send(ApplyEffect {
+                    target,
+                    effect: Effect::new(
+                        EffectKind::Slow,
+                        source,
+                        slow,
+                        duration,
+                        Stacking::StrongestWins,
+                    ),
+                });
+            }
+        }
+    }
+}
+
+impl fmt::Display for OnHit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OnHit::MultiplyDamage { chance, multiplier } => {
+                write!(f, "{}% chance of {}x damage", chance, multiplier)
+            }
+            OnHit::Splash(SplashEffect::Multiplier { multiplier }, Range(range)) => {
+                write!(f, "Splash {}x damage in {} range", multiplier, range)
             }
+            OnHit::SapphireSlow(amount) => write!(f, "Slow by {}", amount),
+            OnHit::EmeraldPoison {
+                dps,
+                slow,
+                duration,
+            } => write!(
+                f,
+                "Poison {} damage per second and slow by {} for {}s",
+                dps, slow, duration
+            ),
         }
     }
 }
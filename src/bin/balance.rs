@@ -0,0 +1,132 @@
+//! Simulates waves against a fixed layout of towers and reports how each tower performed, run it with for instance
+//! `cargo run --release --bin balance -- --layout layouts/rubies.ron --levels 1-10 --seeds 20 --format json`
+
+#![warn(clippy::pedantic)]
+#![allow(clippy::type_complexity)]
+#![allow(clippy::too_many_arguments)]
+#![allow(clippy::too_many_lines)]
+#![allow(clippy::must_use_candidate)]
+#![allow(clippy::needless_pass_by_value)]
+#![allow(clippy::enum_glob_use)]
+
+use anyhow::Context;
+use bevy::prelude::*;
+use rusttd::{
+    cli::{BalanceArgs, Format},
+    maps::{Level, MapDefinition},
+    simulation::{Layout, Simulation},
+    stats::{TowerStats, WaveStats},
+    towers::definitions::TowerDefinitions,
+    waves::WaveDefinitions,
+};
+use serde::Serialize;
+use std::fs;
+
+#[derive(Serialize)]
+struct WaveReport {
+    level: u32,
+    seed: u64,
+    outcome: String,
+    kills: u32,
+    leaked: u32,
+    /// Seconds from the first spawn until the wave was over
+    time_to_clear: f32,
+    towers: Vec<TowerReport>,
+}
+
+#[derive(Serialize)]
+struct TowerReport {
+    name: String,
+    x: i32,
+    y: i32,
+    damage: u64,
+    kills: u32,
+    overkill: u64,
+}
+
+fn main() {
+    let args = BalanceArgs::parse();
+    if let Err(err) = run(&args) {
+        eprintln!("{:?}", err);
+        std::process::exit(1);
+    }
+}
+
+fn run(args: &BalanceArgs) -> anyhow::Result<()> {
+    let read = |path: &str| fs::read(path).with_context(|| format!("Failed to read {}", path));
+    let layout = args
+        .layout
+        .as_deref()
+        .context("Missing --layout, a RON file listing the towers to place")?;
+    let layout = Layout::from_bytes(&read(layout)?)?;
+    let map = MapDefinition::from_bytes(&read(&args.map)?)?;
+    let towers = TowerDefinitions::from_bytes(&read(&args.towers)?)?;
+    let waves = WaveDefinitions::from_bytes(&read(&args.waves)?)?;
+
+    let mut reports = vec![];
+    for level in args.levels.clone() {
+        for seed in 0..args.seeds {
+            let mut simulation = Simulation::new(map.clone(), towers.clone(), waves.clone());
+            simulation.world_mut().insert_resource(Level(level));
+            simulation.set_seed(seed);
+            let entities = simulation.place_layout(&layout)?;
+            let outcome = simulation.run_wave()?;
+
+            let world = simulation.world();
+            let wave = world.get_resource::<WaveStats>().unwrap();
+            reports.push(WaveReport {
+                level,
+                seed,
+                outcome: format!("{:?}", outcome),
+                kills: wave.kills,
+                leaked: wave.leaked,
+                time_to_clear: wave.duration.as_secs_f32(),
+                towers: layout
+                    .towers
+                    .iter()
+                    .zip(entities)
+                    .map(|(placed, entity)| {
+                        let stats = world.get::<TowerStats>(entity).copied().unwrap_or_default();
+                        TowerReport {
+                            name: world
+                                .get::<Name>(entity)
+                                .map_or_else(String::new, |name| name.as_str().to_string()),
+                            x: placed.pos.0,
+                            y: placed.pos.1,
+                            damage: stats.damage,
+                            kills: stats.kills,
+                            overkill: stats.overkill,
+                        }
+                    })
+                    .collect(),
+            });
+        }
+    }
+
+    match args.format {
+        Format::Json => println!("{}", serde_json::to_string_pretty(&reports)?),
+        Format::Csv => {
+            println!("level,seed,outcome,wave_kills,leaked,time_to_clear,tower,x,y,damage,kills,overkill");
+            for wave in &reports {
+                for tower in &wave.towers {
+                    println!(
+                        "{},{},{},{},{},{:.2},{},{},{},{},{},{}",
+                        wave.level,
+                        wave.seed,
+                        wave.outcome,
+                        wave.kills,
+                        wave.leaked,
+                        wave.time_to_clear,
+                        tower.name,
+                        tower.x,
+                        tower.y,
+                        tower.damage,
+                        tower.kills,
+                        tower.overkill
+                    );
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,21 +1,82 @@
-use crate::towers;
+use crate::{creeps, towers};
 use bevy::prelude::{self, *};
 
 pub struct Plugin;
 
 impl prelude::Plugin for Plugin {
     fn build(&self, app: &mut App) {
-        app.add_system(AttackSpeed::apply);
+        app.add_system(Buffs::apply).add_system(Debuffs::apply);
     }
 }
 
-#[derive(Component, PartialEq, PartialOrd)]
-pub struct AttackSpeed(pub f32);
+/// Bonuses of the strongest aura of each kind reaching the tower, kept up to date by `aura::Auras::recompute`
+#[derive(Clone, Component, Copy, Debug, Default, PartialEq)]
+pub struct Buffs {
+    pub attack_speed: f32,
+    /// Percentage added to the damage of every hit
+    pub damage: u32,
+    pub range: f32,
+    /// Percent chance of a hit dealing double damage
+    pub crit_chance: u32,
+    /// Percentage added to the chance of on-hit abilities
+    pub on_hit_chance: u32,
+}
+
+impl Buffs {
+    /// Derives the tower's attack speed and range from its base stats and its buffs, so losing a buff needs nothing
+    /// undone. Damage and chances are applied when the tower hits
+    fn apply(
+        mut towers: Query<(
+            &mut towers::AttackSpeed,
+            &towers::BaseAttackSpeed,
+            &mut towers::Range,
+            &towers::BaseRange,
+            Option<&Buffs>,
+        )>,
+    ) {
+        for (
+            mut speed,
+            towers::BaseAttackSpeed(base_speed),
+            mut range,
+            towers::BaseRange(base_range),
+            buffs,
+        ) in towers.iter_mut()
+        {
+            let buffs = buffs.copied().unwrap_or_default();
+            let total_speed = base_speed + buffs.attack_speed;
+            if (speed.0 - total_speed).abs() > f32::EPSILON {
+                speed.0 = total_speed;
+            }
+            let total_range = base_range + buffs.range;
+            if (range.0 - total_range).abs() > f32::EPSILON {
+                range.0 = total_range;
+            }
+        }
+    }
+
+    /// Scales the damage of a hit by the damage buff
+    pub fn damage(&self, damage: u64) -> u64 {
+        damage * (100 + u64::from(self.damage)) / 100
+    }
+}
+
+/// Penalties of the strongest creep aura of each kind reaching the creep, kept up to date by
+/// `aura::Auras::debuff_creeps`
+#[derive(Clone, Component, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Debuffs {
+    /// Armor removed from the creep
+    pub armor: u32,
+    /// Percentage the speed is reduced by, on top of slow effects
+    pub slow: u32,
+}
 
-impl AttackSpeed {
-    fn apply(mut buff: Query<(&mut towers::AttackSpeed, &AttackSpeed), Added<AttackSpeed>>) {
-        for (mut stat, AttackSpeed(buff)) in buff.iter_mut() {
-            stat.0 += *buff;
+impl Debuffs {
+    /// Derives the creep's armor from its base armor, slows are combined with slow effects by `effects`
+    fn apply(
+        mut creeps: Query<(&mut creeps::Armor, &creeps::BaseArmor, &Debuffs), Changed<Debuffs>>,
+    ) {
+        for (mut armor, creeps::BaseArmor(base), debuffs) in creeps.iter_mut() {
+            armor.0 = base.saturating_sub(debuffs.armor);
         }
     }
 }
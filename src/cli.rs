@@ -0,0 +1,152 @@
+use std::ops::RangeInclusive;
+
+/// Options given on the command line, for instance `rusttd --map level_1 --seed 42`
+pub struct Args {
+    /// Name of the map to play
+    pub map: String,
+    /// Seed for every random roll, a random one is picked if not given
+    pub seed: Option<u64>,
+    /// File the inputs of the game are recorded to
+    pub record: String,
+    /// Replay to play back, overriding the map and seed
+    pub replay: Option<String>,
+    /// File the game is quicksaved to and loaded from
+    pub save: String,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            map: "level_1".to_string(),
+            seed: None,
+            record: "replays/last.replay".to_string(),
+            replay: None,
+            save: "saves/quicksave.save".to_string(),
+        }
+    }
+}
+
+impl Args {
+    pub fn parse() -> Self {
+        let mut args = Self::default();
+        let mut iter = std::env::args().skip(1);
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--map" => {
+                    if let Some(map) = value(&arg, iter.next()) {
+                        args.map = map;
+                    }
+                }
+                "--seed" => {
+                    if let Some(seed) = value(&arg, iter.next()) {
+                        match seed.parse() {
+                            Ok(seed) => args.seed = Some(seed),
+                            Err(_) => {
+                                eprintln!("Invalid seed {}, expected a positive number", seed)
+                            }
+                        }
+                    }
+                }
+                "--record" => {
+                    if let Some(record) = value(&arg, iter.next()) {
+                        args.record = record;
+                    }
+                }
+                "--replay" => {
+                    if let Some(replay) = value(&arg, iter.next()) {
+                        args.replay = Some(replay);
+                    }
+                }
+                "--save" => {
+                    if let Some(save) = value(&arg, iter.next()) {
+                        args.save = save;
+                    }
+                }
+                _ => eprintln!("Ignoring unknown argument {}", arg),
+            }
+        }
+        args
+    }
+}
+
+/// Options of the `balance` binary, for instance `balance --layout layouts/rubies.ron --levels 1-10 --seeds 20`
+pub struct BalanceArgs {
+    /// RON file describing the towers to place, see `simulation::Layout`
+    pub layout: Option<String>,
+    pub levels: RangeInclusive<u32>,
+    /// Number of seeds every level is simulated with, starting at seed 0
+    pub seeds: u64,
+    pub format: Format,
+    pub map: String,
+    pub towers: String,
+    pub waves: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    Json,
+}
+
+impl Default for BalanceArgs {
+    fn default() -> Self {
+        Self {
+            layout: None,
+            levels: 1..=1,
+            seeds: 10,
+            format: Format::Csv,
+            map: "assets/maps/level_1.map".to_string(),
+            towers: "assets/gems.towers".to_string(),
+            waves: "assets/level_1.waves".to_string(),
+        }
+    }
+}
+
+impl BalanceArgs {
+    pub fn parse() -> Self {
+        let mut args = Self::default();
+        let mut iter = std::env::args().skip(1);
+        while let Some(arg) = iter.next() {
+            let value = value(&arg, iter.next());
+            match (arg.as_str(), value) {
+                (_, None) => {}
+                ("--layout", Some(layout)) => args.layout = Some(layout),
+                ("--levels", Some(levels)) => match parse_levels(&levels) {
+                    Some(levels) => args.levels = levels,
+                    None => eprintln!("Invalid level range {}, expected for instance 1-10", levels),
+                },
+                ("--seeds", Some(seeds)) => match seeds.parse() {
+                    Ok(seeds) => args.seeds = seeds,
+                    Err(_) => eprintln!("Invalid seed count {}", seeds),
+                },
+                ("--format", Some(format)) => match format.as_str() {
+                    "csv" => args.format = Format::Csv,
+                    "json" => args.format = Format::Json,
+                    _ => eprintln!("Unknown format {}, expected csv or json", format),
+                },
+                ("--map", Some(map)) => args.map = map,
+                ("--towers", Some(towers)) => args.towers = towers,
+                ("--waves", Some(waves)) => args.waves = waves,
+                _ => eprintln!("Ignoring unknown argument {}", arg),
+            }
+        }
+        args
+    }
+}
+
+/// Parses either a single level or an inclusive range such as `1-10`
+fn parse_levels(levels: &str) -> Option<RangeInclusive<u32>> {
+    let (from, to) = levels.split_once('-').unwrap_or((levels, levels));
+    let (from, to) = (from.parse().ok()?, to.parse().ok()?);
+    if from == 0 || from > to {
+        return None;
+    }
+    Some(from..=to)
+}
+
+fn value(arg: &str, value: Option<String>) -> Option<String> {
+    if value.is_none() {
+        eprintln!("Missing value for argument {}", arg);
+    }
+    value
+}
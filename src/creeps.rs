@@ -1,23 +1,31 @@
 use crate::{
     abilities::OnHitAbilities,
-    grid::Grid,
-    level_1::{
-        map::{self, get_creep},
-        LevelState,
-    },
-    maps::Level,
+    buffs::{Buffs, Debuffs},
+    damage::{self, ArmorType, DamageType},
+    effects::{ApplyEffect, Effects},
+    level_1::{assets::GameState, LevelState},
+    maps::{Level, MapDefinition},
     math_utils,
-    path::resolve,
+    player::{Gold, PlayerHealth},
+    rng::CombatRng,
+    route::CurrentRoute,
+    stats::WaveStats,
     towers::{Damage, ProjectileHit},
+    waves::{Trait, WaveDefinitions},
+};
+use bevy::{
+    math::Vec3Swizzles,
+    prelude::{self, *},
 };
-use bevy::prelude::{self, *};
 use rand::Rng;
+use serde::Deserialize;
 
 pub struct Plugin;
 
 impl prelude::Plugin for Plugin {
     fn build(&self, app: &mut prelude::App) {
         app.add_event::<Death>()
+            .add_event::<DamageDealt>()
             .add_system_set(SystemSet::on_enter(LevelState::Spawning).with_system(start_spawn))
             .add_system_set(
                 SystemSet::on_update(LevelState::Spawning)
@@ -25,25 +33,57 @@ impl prelude::Plugin for Plugin {
                     .with_system(moving)
                     .with_system(death)
                     .with_system(projectile_hit),
-            );
+            )
+            .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(despawn_all));
+    }
+}
+
+pub struct RenderPlugin;
+
+impl prelude::Plugin for RenderPlugin {
+    fn build(&self, app: &mut prelude::App) {
+        app.add_system(render);
     }
 }
 
 #[derive(Component)]
-struct Spawner {
+pub struct Spawner {
     amount: u32,
     timer: Timer,
+    /// Index of the route in `CurrentRoute`
+    route: usize,
+    checkpoints: Vec<(i32, i32)>,
 }
 
-fn start_spawn(mut commands: Commands) {
-    commands.spawn_bundle((
-        Spawner {
-            amount: 10,
-            timer: Timer::from_seconds(1.0, true),
-        },
-        Transform::from_translation(Vec3::new(-20.0, 0.0, 2.0)),
-        GlobalTransform::default(),
-    ));
+impl Spawner {
+    /// Creeps left to spawn
+    pub fn remaining(&self) -> u32 {
+        self.amount
+    }
+}
+
+/// Every spawner of the map spawns the full wave
+#[allow(clippy::cast_precision_loss)]
+fn start_spawn(
+    mut commands: Commands,
+    level: Res<Level>,
+    waves: Res<WaveDefinitions>,
+    map: Res<MapDefinition>,
+) {
+    let wave = waves.get(level.0);
+    for (route, checkpoints) in map.routes().into_iter().enumerate() {
+        let (x, y) = checkpoints[0];
+        commands.spawn_bundle((
+            Spawner {
+                amount: wave.count,
+                timer: Timer::from_seconds(wave.interval, true),
+                route,
+                checkpoints,
+            },
+            Transform::from_translation(Vec3::new(x as f32, 0.0, y as f32)),
+            GlobalTransform::default(),
+        ));
+    }
 }
 
 #[derive(Bundle)]
@@ -52,12 +92,20 @@ pub struct CreepBundle {
     pub movement: Movement,
     pub speed: Speed,
     pub r#type: Type,
+    pub leak: Leak,
+    pub bounty: Bounty,
+    pub base_armor: BaseArmor,
+    pub armor: Armor,
+    pub armor_type: ArmorType,
+    pub effects: Effects,
+    pub debuffs: Debuffs,
 }
 
 #[derive(Component)]
 pub struct Speed {
     pub base: f32,
     pub min: f32,
+    /// Percentage the speed is reduced by, derived from the creep's slow effects and debuffs
     pub modifier: u32,
 }
 
@@ -67,60 +115,93 @@ impl Speed {
         let modified = self.base - (((self.modifier as f32) / 100.0) * self.base);
         self.min.max(modified)
     }
-
-    /// Reduces speed by the given percent modifier, for instance if modifier = 25 the movement speed is reduced by 25%
-    pub fn reduce(&mut self, modifier: u32) {
-        self.modifier += modifier;
-    }
-
-    /// Reverse operation of `Speed::reduce`, may fail if not temporally following a corresponding `Speed::reduce` since modifier is currently unsigned
-    pub fn increase(&mut self, modifier: u32) {
-        self.modifier -= modifier;
-    }
 }
 
 #[derive(Component)]
 pub struct Life(pub u64);
 
+/// Player health lost when the creep reaches the end of its route
+#[derive(Component)]
+pub struct Leak(pub u32);
+
+/// Gold given to the player when the creep is killed
+#[derive(Component)]
+pub struct Bounty(pub u32);
+
+/// Armor including debuffs, derived from `BaseArmor` by `buffs::Debuffs`
+#[derive(Component)]
+pub struct Armor(pub u32);
+
+/// Armor from the wave definitions
+#[derive(Component)]
+pub struct BaseArmor(pub u32);
+
+/// Marks creeps that cannot be slowed
+#[derive(Component)]
+pub struct Immune;
+
 #[derive(Component)]
 pub struct Movement {
     pub route: Vec<(i32, i32)>,
     pub destination: usize,
 }
 
+impl Movement {
+    /// Distance left to the end of the route from the given position, ignoring height
+    #[allow(clippy::cast_precision_loss)]
+    pub fn remaining(&self, position: Vec3) -> f32 {
+        let to_vec = |(x, y): (i32, i32)| Vec2::new(x as f32, y as f32);
+        let mut current = position.xz();
+        let mut remaining = 0.0;
+        for pos in self.route.iter().skip(self.destination) {
+            let next = to_vec(*pos);
+            remaining += current.distance(next);
+            current = next;
+        }
+        remaining
+    }
+}
+
 fn spawn(
     mut commands: Commands,
     time: Res<Time>,
-    grid: Res<Grid>,
+    current_route: Res<CurrentRoute>,
     level: Res<Level>,
-    mut meshes: ResMut<Assets<Mesh>>,
+    waves: Res<WaveDefinitions>,
+    map: Res<MapDefinition>,
     mut spawners: Query<(Entity, &Transform, &mut Spawner)>,
 ) {
     for (spawner_entity, transform, mut spawner) in spawners.iter_mut() {
         spawner.timer.tick(time.delta());
 
         if spawner.timer.just_finished() {
-            let mut creep = get_creep(level.0);
-
-            // Only use pathfinding for ground creeps
-            if let Type::Ground = creep.r#type {
-                let mut route_for_spawner = vec![Grid::to_grid_pos(transform.translation / 2.0)];
-                route_for_spawner.extend(creep.movement.route);
-                creep.movement.route = resolve(&*grid, &route_for_spawner)
-                    .unwrap_or_else(|| map::CREEP_ROUTE.to_vec());
-            }
+            let wave = waves.get(level.0);
+            let mut creep = wave.creep(spawner.checkpoints.clone());
 
-            commands.spawn_bundle(creep).insert_bundle(PbrBundle {
-                mesh: meshes.add(
-                    shape::Icosphere {
-                        radius: 0.5,
-                        subdivisions: 10,
+            let mut transform = *transform;
+            match creep.r#type {
+                Type::Ground => {
+                    // Only use pathfinding for ground creeps
+                    if let Some(route) = current_route.get(spawner.route) {
+                        creep.movement.route = route.clone();
+                    } else {
+                        // Builds that block the path are rejected, so this should never happen
+                        error!(
+                            "No path for ground creeps, walking straight between waypoints instead"
+                        );
                     }
-                    .into(),
-                ),
-                transform: *transform,
-                ..PbrBundle::default()
-            });
+                }
+                Type::Flying => {
+                    // Flying creeps fly straight over towers and rocks between the checkpoints
+                    transform.translation.y = map.flying_altitude;
+                }
+            }
+
+            let mut entity = commands.spawn_bundle(creep);
+            entity.insert_bundle((transform, GlobalTransform::default()));
+            if wave.has_trait(Trait::Immune) {
+                entity.insert(Immune);
+            }
 
             spawner.amount -= 1;
 
@@ -131,6 +212,38 @@ fn spawn(
     }
 }
 
+fn render(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut mats: ResMut<Assets<StandardMaterial>>,
+    creeps: Query<(Entity, &Transform, &Type), Added<Type>>,
+) {
+    for (entity, transform, r#type) in creeps.iter() {
+        let (shape, material) = match r#type {
+            Type::Ground => (
+                shape::Icosphere {
+                    radius: 0.5,
+                    subdivisions: 10,
+                },
+                StandardMaterial::default(),
+            ),
+            Type::Flying => (
+                shape::Icosphere {
+                    radius: 0.4,
+                    subdivisions: 1,
+                },
+                Color::ALICE_BLUE.into(),
+            ),
+        };
+        commands.entity(entity).insert_bundle(PbrBundle {
+            mesh: meshes.add(shape.into()),
+            material: mats.add(material),
+            transform: *transform,
+            ..PbrBundle::default()
+        });
+    }
+}
+
 #[allow(clippy::cast_precision_loss)]
 fn moving(
     time: Res<Time>,
@@ -174,20 +287,52 @@ pub struct Death {
 fn death(
     mut commands: Commands,
     mut level_state: ResMut<State<LevelState>>,
+    mut game_state: ResMut<State<GameState>>,
+    mut health: ResMut<PlayerHealth>,
+    mut gold: ResMut<Gold>,
+    mut stats: ResMut<WaveStats>,
     mut er: EventReader<Death>,
-    creeps: Query<(), With<Type>>,
+    creeps: Query<(Option<&Leak>, Option<&Bounty>), With<Type>>,
+    spawners: Query<(), With<Spawner>>,
 ) {
-    let mut deaths = 0;
+    if *game_state.current() != GameState::Play {
+        return;
+    }
+
+    let mut dead = vec![];
     for Death {
         entity,
-        remaining_life: _remaining_life,
+        remaining_life,
     } in er.iter()
     {
-        deaths += 1;
+        // A creep can die several times in a frame before being despawned, for instance by splash and a projectile
+        if dead.contains(entity) {
+            continue;
+        }
+        dead.push(*entity);
+
+        if let Ok((leak, bounty)) = creeps.get(*entity) {
+            match (remaining_life, leak, bounty) {
+                (Some(_), Some(Leak(leak)), _) => {
+                    stats.leaked += 1;
+                    if health.damage(*leak) {
+                        game_state
+                            .set(GameState::GameOver)
+                            .map_err(|err| error!("Failed to set game state to GameOver: {}", err))
+                            .ok();
+                        return;
+                    }
+                }
+                (None, _, Some(Bounty(bounty))) => gold.0 += bounty,
+                _ => {}
+            }
+        }
 
         commands.entity(*entity).despawn_recursive();
     }
-    if deaths > 0 && creeps.iter().count() <= deaths {
+    // Creeps can all be dead while spawners still have more to spawn
+    let deaths = dead.len();
+    if deaths > 0 && spawners.is_empty() && creeps.iter().count() <= deaths {
         level_state
             .set(LevelState::Building)
             .map_err(|err| error!("Failed to set level state to building: {}", err))
@@ -195,44 +340,114 @@ fn death(
     }
 }
 
+fn despawn_all(mut commands: Commands, entities: Query<Entity, Or<(With<Type>, With<Spawner>)>>) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
 fn projectile_hit(
     mut commands: Commands,
+    mut rng: ResMut<CombatRng>,
     mut er: EventReader<ProjectileHit>,
-    mut ew: EventWriter<Death>,
-    towers: Query<(&Damage, &OnHitAbilities)>,
-    mut creeps: Query<(Entity, &GlobalTransform, &mut Life)>,
+    mut deaths: EventWriter<Death>,
+    mut dealt: EventWriter<DamageDealt>,
+    mut effects: EventWriter<ApplyEffect>,
+    towers: Query<(&Damage, &DamageType, &OnHitAbilities, Option<&Buffs>)>,
+    mut creeps: Query<(Entity, &GlobalTransform, &Armor, &ArmorType, &mut Life)>,
 ) {
     for ProjectileHit(projectile) in er.iter() {
-        if let Ok((target, position, mut life)) = creeps.get_mut(projectile.target) {
-            if let Ok((damage, OnHitAbilities(abilities))) = towers.get(projectile.origin) {
-                let mut damage = match damage {
-                    Damage::Range(range) => rand::thread_rng().gen_range(range.clone()),
+        if let Ok((target, position, armor, armor_type, mut life)) =
+            creeps.get_mut(projectile.target)
+        {
+            if let Ok((damage, damage_type, OnHitAbilities(abilities), buffs)) =
+                towers.get(projectile.origin)
+            {
+                let buffs = buffs.copied().unwrap_or_default();
+                let mut damage = buffs.damage(match damage {
+                    Damage::Range(range) => rng.0.gen_range(range.clone()),
                     Damage::Fixed(val) => *val,
-                };
+                });
+                // Only rolled with a crit buff, so towers without one draw the same numbers as before
+                if buffs.crit_chance > 0 && rng.0.gen_range(0..100) < buffs.crit_chance {
+                    damage *= 2;
+                }
                 for on_hit in abilities {
-                    on_hit.apply(target, &mut commands, &mut damage, position.translation);
+                    on_hit.with_bonus_chance(buffs.on_hit_chance).apply(
+                        projectile.origin,
+                        target,
+                        &mut commands,
+                        &mut effects,
+                        &mut damage,
+                        *damage_type,
+                        position.translation,
+                        &mut rng.0,
+                    );
                 }
-                damage_creep(projectile.target, damage, &mut life, &mut ew);
+                damage_creep(
+                    projectile.origin,
+                    projectile.target,
+                    damage,
+                    *damage_type,
+                    armor,
+                    *armor_type,
+                    &mut life,
+                    &mut deaths,
+                    &mut dealt,
+                );
             }
         }
     }
 }
 
-pub fn damage_creep(target: Entity, damage: u64, mut life: &mut Life, ew: &mut EventWriter<Death>) {
-    if life.0 >= damage {
-        life.0 -= damage;
-    } else {
-        life.0 = 0;
-    }
+/// Damages the target creep on behalf of the tower `source`, after reducing the damage by the creep's armor
+pub fn damage_creep(
+    source: Entity,
+    target: Entity,
+    damage: u64,
+    damage_type: DamageType,
+    Armor(armor): &Armor,
+    armor_type: ArmorType,
+    life: &mut Life,
+    deaths: &mut EventWriter<Death>,
+    dealt: &mut EventWriter<DamageDealt>,
+) {
+    let damage = damage::resolve(damage, damage_type, *armor, armor_type);
+    let applied = damage.min(life.0);
+    life.0 -= applied;
+    dealt.send(DamageDealt {
+        source,
+        target,
+        amount: applied,
+        overkill: damage - applied,
+        killed: applied > 0 && life.0 == 0,
+    });
     if life.0 == 0 {
-        ew.send(Death {
+        deaths.send(Death {
             remaining_life: None,
             entity: target,
         });
     }
 }
 
+/// Sent whenever a creep is damaged, including hits on creeps that already died this frame
+pub struct DamageDealt {
+    /// Tower the damage is attributed to
+    pub source: Entity,
+    pub target: Entity,
+    /// Damage that actually reduced the creep's life
+    pub amount: u64,
+    /// Damage exceeding the creep's remaining life
+    pub overkill: u64,
+    /// Whether this hit brought the creep to 0 life
+    pub killed: bool,
+}
+
+/// Tower that damage dealt by an entity other than the tower itself, such as a splash, is attributed to
 #[derive(Clone, Component, Copy)]
+pub struct DamageSource(pub Entity);
+
+#[derive(Clone, Component, Copy, Deserialize)]
 pub enum Type {
     Ground,
     Flying,
@@ -1,8 +1,10 @@
 use crate::{
     grid,
-    level_1::LevelState,
-    maps::Ground,
-    towers::{BuildGem, ChooseGem},
+    level_1::{assets::GameState, LevelState},
+    maps::{Ground, MapDefinition},
+    path,
+    replay::Playback,
+    towers::{recipes::CombineRecipe, BuildGem, ChooseGem, CombineGem},
 };
 use bevy::prelude::{self, shape::Plane, *};
 use bevy_mod_picking::PickingCamera;
@@ -48,6 +50,8 @@ fn render_grid(
     mut commands: Commands,
     show: Res<ShowGrid>,
     grid: Res<grid::Grid>,
+    map: Option<Res<MapDefinition>>,
+    mut validity: Local<Option<(Vec3, bool)>>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut cursors: Query<(Entity, &mut Transform, &mut Handle<StandardMaterial>), With<Grid>>,
@@ -71,18 +75,34 @@ fn render_grid(
 
         let grid_pos = grid::Grid::snap_to_grid(intersection.position());
 
-        let material = materials.add(
-            if grid.buildable(&[
-                grid::Grid::to_grid_pos(grid_pos),
-                grid::Grid::to_grid_pos(Vec3::new(grid_pos.x + 1.0, grid_pos.y, grid_pos.z)),
-                grid::Grid::to_grid_pos(Vec3::new(grid_pos.x, grid_pos.y, grid_pos.z + 1.0)),
-                grid::Grid::to_grid_pos(Vec3::new(grid_pos.x + 1.0, grid_pos.y, grid_pos.z + 1.0)),
-            ]) {
-                Color::BLUE.into()
-            } else {
-                Color::RED.into()
-            },
-        );
+        // Checking for blocked paths is expensive, so only do it when the cursor or the grid changes
+        let valid = match *validity {
+            Some((pos, valid)) if pos == grid_pos && !grid.is_changed() => valid,
+            _ => {
+                let positions = [
+                    grid::Grid::to_grid_pos(grid_pos),
+                    grid::Grid::to_grid_pos(Vec3::new(grid_pos.x + 1.0, grid_pos.y, grid_pos.z)),
+                    grid::Grid::to_grid_pos(Vec3::new(grid_pos.x, grid_pos.y, grid_pos.z + 1.0)),
+                    grid::Grid::to_grid_pos(Vec3::new(
+                        grid_pos.x + 1.0,
+                        grid_pos.y,
+                        grid_pos.z + 1.0,
+                    )),
+                ];
+                let valid = grid.buildable(&positions)
+                    && map.map_or(true, |map| {
+                        !path::would_block(&grid, &map.routes(), &positions)
+                    });
+                *validity = Some((grid_pos, valid));
+                valid
+            }
+        };
+
+        let material = materials.add(if valid {
+            Color::BLUE.into()
+        } else {
+            Color::RED.into()
+        });
 
         if let Ok((_, mut transform, mut mat)) = cursors.get_single_mut() {
             if transform.translation != grid_pos {
@@ -116,11 +136,18 @@ fn despawn_cursor(
 }
 
 fn build_on_click(
+    game_state: Res<State<GameState>>,
+    playback: Option<Res<Playback>>,
     mut er: EventReader<OnActionBegin>,
     mut ew: EventWriter<BuildGem>,
     cameras: Query<&PickingCamera>,
     ground: Query<(), With<Ground>>,
 ) {
+    // The level returns to building when the game is over, but nothing should be built until it restarts
+    if *game_state.current() != GameState::Play || playback.is_some() {
+        return;
+    }
+
     for action in er.iter() {
         if action.action != "LEFT_CLICK" {
             continue;
@@ -145,13 +172,23 @@ fn build_on_click(
 }
 
 fn choose_on_click(
+    playback: Option<Res<Playback>>,
     mut er: EventReader<OnActionBegin>,
-    mut ew: EventWriter<ChooseGem>,
+    mut choose: EventWriter<ChooseGem>,
+    mut combine: EventWriter<CombineGem>,
+    mut recipe: EventWriter<CombineRecipe>,
     cameras: Query<&PickingCamera>,
     ground: Query<(), With<Ground>>,
 ) {
+    if playback.is_some() {
+        return;
+    }
+
     for action in er.iter() {
-        if action.action != "LEFT_CLICK" {
+        if !matches!(
+            action.action.as_str(),
+            "LEFT_CLICK" | "RIGHT_CLICK" | "MIDDLE_CLICK"
+        ) {
             continue;
         }
         let camera = cameras.single();
@@ -168,6 +205,10 @@ fn choose_on_click(
 
         let grid_pos = grid::Grid::to_grid_pos(intersection.position());
 
-        ew.send(ChooseGem { pos: grid_pos });
+        match action.action.as_str() {
+            "LEFT_CLICK" => choose.send(ChooseGem { pos: grid_pos }),
+            "RIGHT_CLICK" => combine.send(CombineGem { pos: grid_pos }),
+            _ => recipe.send(CombineRecipe { pos: grid_pos }),
+        }
     }
 }
@@ -0,0 +1,86 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+/// Kind of damage a tower deals, every kind is strong or weak against some armor types
+#[derive(Clone, Component, Copy, Debug, Deserialize, PartialEq, Eq)]
+pub enum DamageType {
+    Normal,
+    Pierce,
+    Magic,
+    Siege,
+    /// Ignores the armor type, armor still reduces it
+    Pure,
+}
+
+impl Default for DamageType {
+    fn default() -> Self {
+        DamageType::Normal
+    }
+}
+
+#[derive(Clone, Component, Copy, Debug, Deserialize, PartialEq, Eq)]
+pub enum ArmorType {
+    Normal,
+    Light,
+    Heavy,
+    Fortified,
+}
+
+impl Default for ArmorType {
+    fn default() -> Self {
+        ArmorType::Normal
+    }
+}
+
+impl DamageType {
+    /// Percentage of the damage dealt to the given armor type
+    pub fn multiplier(self, armor: ArmorType) -> u64 {
+        use ArmorType::*;
+        match (self, armor) {
+            (DamageType::Normal, Fortified) => 70,
+            (DamageType::Pierce, Light) => 150,
+            (DamageType::Pierce, Heavy) => 75,
+            (DamageType::Pierce, Fortified) | (DamageType::Magic, Fortified) => 50,
+            (DamageType::Magic, Light) => 125,
+            (DamageType::Magic, Heavy) | (DamageType::Siege, Fortified) => 150,
+            (DamageType::Siege, Light) => 75,
+            _ => 100,
+        }
+    }
+}
+
+/// Damage left of a hit after the armor type multiplier and the armor reduction, each point of armor reduces
+/// damage by a diminishing amount: 6% for the first point, 50% at 17 points
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss
+)]
+pub fn resolve(damage: u64, damage_type: DamageType, armor: u32, armor_type: ArmorType) -> u64 {
+    let damage = damage * damage_type.multiplier(armor_type) / 100;
+    let armor = armor as f32 * 0.06;
+    (damage as f32 / (1.0 + armor)).round() as u64
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn no_armor() {
+        assert_eq!(resolve(100, DamageType::Normal, 0, ArmorType::Normal), 100);
+    }
+
+    #[test]
+    fn armor_reduces_damage() {
+        assert_eq!(resolve(100, DamageType::Normal, 1, ArmorType::Normal), 94);
+        assert_eq!(resolve(100, DamageType::Pure, 17, ArmorType::Fortified), 50);
+    }
+
+    #[test]
+    fn type_multipliers() {
+        assert_eq!(resolve(100, DamageType::Pierce, 0, ArmorType::Light), 150);
+        assert_eq!(resolve(100, DamageType::Magic, 0, ArmorType::Fortified), 50);
+        assert_eq!(resolve(100, DamageType::Pure, 0, ArmorType::Fortified), 100);
+    }
+}
@@ -0,0 +1,366 @@
+use crate::{
+    buffs::Debuffs,
+    creeps::{self, Armor, DamageDealt, Death, Immune, Life, Speed},
+    damage::{ArmorType, DamageType},
+    level_1::LevelState,
+};
+use bevy::prelude::{self, *};
+use std::time::Duration;
+
+/// Timed effects on creeps such as slows and poisons. Effects are only ever added to and removed from `Effects`,
+/// what they do to the creep is derived from the effects left each frame, so nothing has to be undone on expiry
+pub struct Plugin;
+
+impl prelude::Plugin for Plugin {
+    fn build(&self, app: &mut prelude::App) {
+        app.add_event::<ApplyEffect>()
+            .add_event::<EffectTick>()
+            .add_system_set(
+                SystemSet::on_update(LevelState::Spawning)
+                    .with_system(apply_effects.label("Apply effects"))
+                    .with_system(
+                        update_effects
+                            .label("Update effects")
+                            .after("Apply effects"),
+                    )
+                    .with_system(slow.after("Update effects"))
+                    .with_system(poison.after("Update effects")),
+            );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EffectKind {
+    /// Reduces speed by the magnitude in percent
+    Slow,
+    /// Deals the magnitude as damage every tick
+    Poison,
+}
+
+/// How an effect combines with effects of the same kind already on the creep
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stacking {
+    /// One effect per source, applying it again replaces it and restarts its duration
+    Refresh,
+    /// One effect in total, applying a weaker one does nothing even if it would last longer
+    StrongestWins,
+    /// Every application adds to the magnitude until the cap is reached, each expiring on its own
+    Additive { cap: u32 },
+}
+
+#[derive(Clone, Debug)]
+pub struct Effect {
+    pub kind: EffectKind,
+    /// Tower the effect came from, damage dealt by its ticks is attributed to it
+    pub source: Entity,
+    pub magnitude: u32,
+    pub stacking: Stacking,
+    pub damage_type: DamageType,
+    duration: Timer,
+    /// Fires a tick every time it finishes, for effects acting over time
+    tick: Option<Timer>,
+}
+
+impl Effect {
+    pub fn new(
+        kind: EffectKind,
+        source: Entity,
+        magnitude: u32,
+        duration: f32,
+        stacking: Stacking,
+    ) -> Self {
+        Self {
+            kind,
+            source,
+            magnitude,
+            stacking,
+            damage_type: DamageType::default(),
+            duration: Timer::from_seconds(duration, false),
+            tick: None,
+        }
+    }
+
+    /// Ticks every `interval` seconds while the effect lasts
+    pub fn with_tick(mut self, interval: f32) -> Self {
+        self.tick = Some(Timer::from_seconds(interval, true));
+        self
+    }
+
+    pub fn with_damage_type(mut self, damage_type: DamageType) -> Self {
+        self.damage_type = damage_type;
+        self
+    }
+}
+
+/// Every effect currently on a creep
+#[derive(Component, Default)]
+pub struct Effects(Vec<Effect>);
+
+impl Effects {
+    /// Adds the effect following its stacking policy
+    pub fn apply(&mut self, effect: Effect) {
+        match effect.stacking {
+            Stacking::Refresh => {
+                if let Some(existing) = self.0.iter_mut().find(|existing| {
+                    existing.kind == effect.kind && existing.source == effect.source
+                }) {
+                    *existing = effect;
+                } else {
+                    self.0.push(effect);
+                }
+            }
+            Stacking::StrongestWins => {
+                if self.0.iter().any(|existing| {
+                    existing.kind == effect.kind && existing.magnitude > effect.magnitude
+                }) {
+                    return;
+                }
+                self.0.retain(|existing| existing.kind != effect.kind);
+                self.0.push(effect);
+            }
+            Stacking::Additive { .. } => self.0.push(effect),
+        }
+    }
+
+    /// Combined magnitude of every effect of the given kind, capped by the lowest cap of additive effects
+    pub fn magnitude(&self, kind: EffectKind) -> u32 {
+        let effects = || self.0.iter().filter(|effect| effect.kind == kind);
+        let total = effects()
+            .map(|effect| effect.magnitude)
+            .fold(0, u32::saturating_add);
+        let cap = effects()
+            .filter_map(|effect| match effect.stacking {
+                Stacking::Additive { cap } => Some(cap),
+                _ => None,
+            })
+            .min()
+            .unwrap_or(u32::MAX);
+        total.min(cap)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Advances every effect, calling `on_tick` for each tick and dropping the effects that ran out. An effect ticking
+    /// on the frame it expires still gets its last tick
+    fn update(&mut self, delta: Duration, mut on_tick: impl FnMut(&Effect)) {
+        for effect in &mut self.0 {
+            if let Some(tick) = &mut effect.tick {
+                tick.tick(delta);
+                for _ in 0..tick.times_finished() {
+                    on_tick(effect);
+                }
+            }
+            effect.duration.tick(delta);
+        }
+        self.0.retain(|effect| !effect.duration.finished());
+    }
+}
+
+/// Adds an effect to the target creep
+pub struct ApplyEffect {
+    pub target: Entity,
+    pub effect: Effect,
+}
+
+/// Sent every time a ticking effect ticks
+pub struct EffectTick {
+    pub target: Entity,
+    pub kind: EffectKind,
+    pub source: Entity,
+    pub magnitude: u32,
+    pub damage_type: DamageType,
+}
+
+fn apply_effects(mut er: EventReader<ApplyEffect>, mut creeps: Query<&mut Effects>) {
+    for ApplyEffect { target, effect } in er.iter() {
+        // The target may have died from the hit applying the effect
+        if let Ok(mut effects) = creeps.get_mut(*target) {
+            effects.apply(effect.clone());
+        }
+    }
+}
+
+fn update_effects(
+    time: Res<Time>,
+    mut ew: EventWriter<EffectTick>,
+    mut creeps: Query<(Entity, &mut Effects)>,
+) {
+    for (target, mut effects) in creeps.iter_mut() {
+        if effects.is_empty() {
+            continue;
+        }
+        effects.update(time.delta(), |effect| {
+            ew.send(EffectTick {
+                target,
+                kind: effect.kind,
+                source: effect.source,
+                magnitude: effect.magnitude,
+                damage_type: effect.damage_type,
+            });
+        });
+    }
+}
+
+/// Slow effects and slow auras add up
+fn slow(
+    mut creeps: Query<
+        (&Effects, &Debuffs, &mut Speed, Option<&Immune>),
+        Or<(Changed<Effects>, Changed<Debuffs>)>,
+    >,
+) {
+    for (effects, debuffs, mut speed, immune) in creeps.iter_mut() {
+        speed.modifier = if immune.is_some() {
+            0
+        } else {
+            effects
+                .magnitude(EffectKind::Slow)
+                .saturating_add(debuffs.slow)
+        };
+    }
+}
+
+fn poison(
+    mut er: EventReader<EffectTick>,
+    mut deaths: EventWriter<Death>,
+    mut dealt: EventWriter<DamageDealt>,
+    mut creeps: Query<(&mut Life, &Armor, &ArmorType)>,
+) {
+    for tick in er.iter() {
+        if tick.kind != EffectKind::Poison {
+            continue;
+        }
+        if let Ok((mut life, armor, armor_type)) = creeps.get_mut(tick.target) {
+            creeps::damage_creep(
+                tick.source,
+                tick.target,
+                u64::from(tick.magnitude),
+                tick.damage_type,
+                armor,
+                *armor_type,
+                &mut life,
+                &mut deaths,
+                &mut dealt,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn slow(source: u32, magnitude: u32, duration: f32, stacking: Stacking) -> Effect {
+        Effect::new(
+            EffectKind::Slow,
+            Entity::new(source),
+            magnitude,
+            duration,
+            stacking,
+        )
+    }
+
+    fn advance(effects: &mut Effects, seconds: f32) -> u32 {
+        let mut ticks = 0;
+        effects.update(Duration::from_secs_f32(seconds), |_| ticks += 1);
+        ticks
+    }
+
+    #[test]
+    fn refresh_restarts_duration() {
+        let mut effects = Effects::default();
+        effects.apply(slow(0, 20, 2.0, Stacking::Refresh));
+        advance(&mut effects, 1.5);
+        effects.apply(slow(0, 10, 2.0, Stacking::Refresh));
+        advance(&mut effects, 1.0);
+        assert_eq!(effects.magnitude(EffectKind::Slow), 10);
+        advance(&mut effects, 1.0);
+        assert!(effects.is_empty());
+    }
+
+    #[test]
+    fn refresh_keeps_other_sources() {
+        let mut effects = Effects::default();
+        effects.apply(slow(0, 20, 2.0, Stacking::Refresh));
+        effects.apply(slow(1, 10, 2.0, Stacking::Refresh));
+        assert_eq!(effects.magnitude(EffectKind::Slow), 30);
+    }
+
+    #[test]
+    fn strongest_wins() {
+        let mut effects = Effects::default();
+        effects.apply(slow(0, 20, 1.0, Stacking::StrongestWins));
+        // Weaker, even though it would last longer
+        effects.apply(slow(1, 10, 5.0, Stacking::StrongestWins));
+        assert_eq!(effects.magnitude(EffectKind::Slow), 20);
+        advance(&mut effects, 1.0);
+        assert!(effects.is_empty());
+
+        effects.apply(slow(0, 20, 1.0, Stacking::StrongestWins));
+        effects.apply(slow(1, 30, 1.0, Stacking::StrongestWins));
+        assert_eq!(effects.magnitude(EffectKind::Slow), 30);
+    }
+
+    #[test]
+    fn strongest_wins_refreshes_equal() {
+        let mut effects = Effects::default();
+        effects.apply(slow(0, 20, 1.0, Stacking::StrongestWins));
+        advance(&mut effects, 0.5);
+        effects.apply(slow(1, 20, 1.0, Stacking::StrongestWins));
+        advance(&mut effects, 0.75);
+        assert_eq!(effects.magnitude(EffectKind::Slow), 20);
+    }
+
+    #[test]
+    fn additive_is_capped() {
+        let mut effects = Effects::default();
+        for _ in 0..5 {
+            effects.apply(slow(0, 15, 1.0, Stacking::Additive { cap: 50 }));
+        }
+        assert_eq!(effects.magnitude(EffectKind::Slow), 50);
+    }
+
+    #[test]
+    fn additive_stacks_expire_separately() {
+        let mut effects = Effects::default();
+        effects.apply(slow(0, 15, 1.0, Stacking::Additive { cap: 50 }));
+        advance(&mut effects, 0.5);
+        effects.apply(slow(0, 15, 1.0, Stacking::Additive { cap: 50 }));
+        advance(&mut effects, 0.5);
+        assert_eq!(effects.magnitude(EffectKind::Slow), 15);
+    }
+
+    #[test]
+    fn kinds_do_not_stack() {
+        let mut effects = Effects::default();
+        effects.apply(slow(0, 20, 1.0, Stacking::StrongestWins));
+        effects.apply(Effect::new(
+            EffectKind::Poison,
+            Entity::new(0),
+            5,
+            1.0,
+            Stacking::StrongestWins,
+        ));
+        assert_eq!(effects.magnitude(EffectKind::Slow), 20);
+        assert_eq!(effects.magnitude(EffectKind::Poison), 5);
+    }
+
+    #[test]
+    fn last_tick_on_expiry() {
+        let mut effects = Effects::default();
+        effects.apply(
+            Effect::new(
+                EffectKind::Poison,
+                Entity::new(0),
+                5,
+                3.0,
+                Stacking::Refresh,
+            )
+            .with_tick(1.0),
+        );
+        let ticks = (0..3).map(|_| advance(&mut effects, 1.0)).sum::<u32>();
+        assert_eq!(ticks, 3);
+        assert!(effects.is_empty());
+    }
+}
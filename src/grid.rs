@@ -1,3 +1,4 @@
+use crate::maps::Bounds;
 use bevy::{
     math::Vec3Swizzles,
     prelude::{self, *},
@@ -18,12 +19,26 @@ pub enum Slot {
 }
 
 #[derive(Default)]
-pub struct Grid(HashMap<(i32, i32), Slot>);
+pub struct Grid {
+    slots: HashMap<(i32, i32), Slot>,
+    bounds: Bounds,
+    /// Cost for creeps to walk over a tile, tiles without a weight cost 1
+    weights: HashMap<(i32, i32), u32>,
+    /// Bumped every time the grid is reset, since slots are otherwise only ever occupied
+    generation: u32,
+}
 
 impl Grid {
+    pub fn new(bounds: Bounds) -> Self {
+        Self {
+            bounds,
+            ..Self::default()
+        }
+    }
+
     pub fn buildable(&self, pos: &[(i32, i32)]) -> bool {
         for pos in pos {
-            if self.0.contains_key(pos) {
+            if !self.contains(*pos) || self.slots.contains_key(pos) {
                 return false;
             }
         }
@@ -33,7 +48,7 @@ impl Grid {
     pub fn add_building(&mut self, pos: &[(i32, i32)], entity: Entity) -> Result<(), ()> {
         if self.buildable(pos) {
             for pos in pos {
-                self.0.insert(*pos, Slot::Occupied(entity));
+                self.slots.insert(*pos, Slot::Occupied(entity));
             }
             Ok(())
         } else {
@@ -51,13 +66,25 @@ impl Grid {
         (pos.x as i32, pos.y as i32)
     }
 
-    pub fn clear(&mut self) {
-        self.0.clear();
+    /// Empties the grid and resizes it to the given bounds
+    pub fn reset(&mut self, bounds: Bounds) {
+        *self = Self {
+            generation: self.generation.wrapping_add(1),
+            ..Self::new(bounds)
+        };
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    pub fn contains(&self, pos: (i32, i32)) -> bool {
+        self.bounds.contains(pos)
     }
 
     pub fn block(&mut self, pos: (i32, i32)) -> Result<(), ()> {
         if self.buildable(&[pos]) {
-            self.0.insert(pos, Slot::Blocked);
+            self.slots.insert(pos, Slot::Blocked);
             Ok(())
         } else {
             Err(())
@@ -65,11 +92,24 @@ impl Grid {
     }
 
     pub fn keys(&self) -> std::collections::hash_map::Keys<(i32, i32), Slot> {
-        self.0.keys()
+        self.slots.keys()
+    }
+
+    pub fn set_weight(&mut self, pos: (i32, i32), weight: u32) {
+        self.weights.insert(pos, weight);
+    }
+
+    pub fn weight(&self, pos: (i32, i32)) -> u32 {
+        self.weights.get(&pos).copied().unwrap_or(1)
+    }
+
+    /// Whether creeps can walk over the given position, blocked tiles are walkable
+    pub fn walkable(&self, pos: (i32, i32)) -> bool {
+        self.contains(pos) && self.get(pos).is_none()
     }
 
     pub fn get(&self, pos: (i32, i32)) -> Option<Entity> {
-        match self.0.get(&pos)? {
+        match self.slots.get(&pos)? {
             Slot::Occupied(entity) => Some(*entity),
             Slot::Blocked => None,
         }
@@ -0,0 +1,149 @@
+use crate::{
+    creeps::{self, Spawner},
+    level_1::{
+        assets::{Fonts, GameState},
+        LevelState,
+    },
+    maps::{Level, MapDefinition},
+    player::{Gold, PlayerHealth},
+    rng::Seed,
+    towers::JustBuilt,
+    waves::WaveDefinitions,
+};
+use bevy::prelude::{self, *};
+
+pub struct Plugin;
+
+impl prelude::Plugin for Plugin {
+    fn build(&self, app: &mut prelude::App) {
+        app.add_system_set(SystemSet::on_exit(GameState::Loading).with_system(setup))
+            .add_system(update_seed)
+            .add_system(update_status);
+    }
+}
+
+#[derive(Component)]
+struct SeedText;
+
+/// Level, phase, creeps, lives, gold and the next wave in the top left corner
+#[derive(Component)]
+struct StatusText;
+
+fn setup(mut commands: Commands, fonts: Res<Fonts>, seed: Res<Seed>) {
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(5.0),
+                    left: Val::Px(10.0),
+                    ..Rect::default()
+                },
+                ..Style::default()
+            },
+            text: Text::with_section(
+                String::new(),
+                TextStyle {
+                    font: fonts.main.clone(),
+                    font_size: 24.0,
+                    color: Color::WHITE,
+                },
+                TextAlignment::default(),
+            ),
+            ..TextBundle::default()
+        })
+        .insert(StatusText);
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    bottom: Val::Px(5.0),
+                    right: Val::Px(10.0),
+                    ..Rect::default()
+                },
+                ..Style::default()
+            },
+            text: Text::with_section(
+                seed_text(*seed),
+                TextStyle {
+                    font: fonts.main.clone(),
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                },
+                TextAlignment::default(),
+            ),
+            ..TextBundle::default()
+        })
+        .insert(SeedText);
+}
+
+fn update_seed(seed: Res<Seed>, mut texts: Query<&mut Text, With<SeedText>>) {
+    if !seed.is_changed() {
+        return;
+    }
+    for mut text in texts.iter_mut() {
+        text.sections[0].value = seed_text(*seed);
+    }
+}
+
+fn seed_text(Seed(seed): Seed) -> String {
+    format!("Seed {}", seed)
+}
+
+/// Rewritten every frame, the creep and gem counts change too often to track
+#[allow(clippy::cast_possible_truncation)]
+fn update_status(
+    game_state: Res<State<GameState>>,
+    level_state: Res<State<LevelState>>,
+    level: Res<Level>,
+    health: Res<PlayerHealth>,
+    gold: Res<Gold>,
+    map: Option<Res<MapDefinition>>,
+    waves: Option<Res<WaveDefinitions>>,
+    creeps: Query<(), With<creeps::Type>>,
+    spawners: Query<&Spawner>,
+    just_built: Query<(), With<JustBuilt>>,
+    mut texts: Query<&mut Text, With<StatusText>>,
+) {
+    let phase = if *game_state.current() == GameState::GameOver {
+        "Game over, press enter to restart".to_string()
+    } else {
+        match level_state.current() {
+            LevelState::Building => format!("Build 5 gems ({}/5)", just_built.iter().count()),
+            LevelState::Choosing => "Choose one gem to keep".to_string(),
+            LevelState::Spawning => "Wave in progress".to_string(),
+        }
+    };
+    let remaining =
+        creeps.iter().count() as u32 + spawners.iter().map(Spawner::remaining).sum::<u32>();
+    let mut status = format!(
+        "Level {}\n{}\nCreeps remaining: {}\nLives: {}\nGold: {}",
+        level.0, phase, remaining, health.0, gold.0
+    );
+
+    // The wave of the current level is the next one until it starts spawning
+    if let (Some(map), Some(waves)) = (map, waves) {
+        let next = if *level_state.current() == LevelState::Spawning {
+            level.0 + 1
+        } else {
+            level.0
+        };
+        let wave = waves.get(next);
+        let r#type = match wave.r#type {
+            creeps::Type::Ground => "ground",
+            creeps::Type::Flying => "flying",
+        };
+        status.push_str(&format!(
+            "\nNext wave: {} {} creeps with {} life",
+            // Every route spawns the full wave
+            wave.count * map.routes().len() as u32,
+            r#type,
+            wave.life
+        ));
+    }
+
+    for mut text in texts.iter_mut() {
+        text.sections[0].value = status.clone();
+    }
+}
@@ -1,13 +1,23 @@
-use crate::assets::GameState;
+use crate::{
+    level_1::{assets::GameState, LevelState, Restart},
+    player::UpgradeQuality,
+    replay::Playback,
+    save::{LoadGame, SaveGame},
+    workarounds::clear_input_events,
+};
 use bevy::prelude::*;
-use kurinji::Kurinji;
+use kurinji::{Kurinji, OnActionBegin};
 use std::fs::read_to_string;
 
 pub struct Plugin;
 
 impl bevy::prelude::Plugin for Plugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.add_system_set(SystemSet::on_enter(GameState::Play).with_system(setup));
+        app.add_system_set(SystemSet::on_enter(GameState::Play).with_system(setup))
+            .add_system_set(
+                SystemSet::on_enter(LevelState::Choosing).with_system(clear_input_events),
+            )
+            .add_system(actions);
     }
 }
 
@@ -15,3 +25,24 @@ fn setup(mut kurinji: ResMut<Kurinji>) {
     let binding_ron = read_to_string("config/key_mappings.ron").unwrap();
     kurinji.set_bindings_with_ron(&binding_ron);
 }
+
+/// Turns key presses into gameplay events, so the gameplay plugins never depend on kurinji
+fn actions(
+    playback: Option<Res<Playback>>,
+    mut er: EventReader<OnActionBegin>,
+    mut restart: EventWriter<Restart>,
+    mut upgrade_quality: EventWriter<UpgradeQuality>,
+    mut save: EventWriter<SaveGame>,
+    mut load: EventWriter<LoadGame>,
+) {
+    for action in er.iter() {
+        match action.action.as_str() {
+            "RESTART" => restart.send(Restart),
+            // Replays purchase quality levels themselves
+            "UPGRADE_QUALITY" if playback.is_none() => upgrade_quality.send(UpgradeQuality),
+            "SAVE" => save.send(SaveGame),
+            "LOAD" => load.send(LoadGame),
+            _ => {}
+        }
+    }
+}
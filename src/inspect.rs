@@ -0,0 +1,296 @@
+use crate::{
+    abilities::{aura::Auras, OnHitAbilities},
+    buffs::Buffs,
+    damage::DamageType,
+    grid::Grid,
+    level_1::assets::{Fonts, GameState},
+    maps::Ground,
+    replay::Playback,
+    stats::TowerStats,
+    towers::{
+        targeting::{SetTargeting, TargetingMode},
+        AttackSpeed, Damage, Range, Tower,
+    },
+};
+use bevy::prelude::{self, *};
+use bevy_mod_picking::PickingCamera;
+use kurinji::OnActionBegin;
+use std::fmt;
+
+/// Clicking a tower selects it, showing its stats in a panel and its range as a ring on the ground. The
+/// `CYCLE_TARGETING` action changes the targeting mode of the selected tower
+pub struct Plugin;
+
+impl prelude::Plugin for Plugin {
+    fn build(&self, app: &mut prelude::App) {
+        app.insert_resource(Selected(None))
+            .add_system_set(SystemSet::on_exit(GameState::Loading).with_system(setup))
+            .add_system(select_on_click.label("Select tower"))
+            .add_system(deselect_removed.after("Select tower").label("Deselect"))
+            .add_system(cycle_targeting.after("Deselect"))
+            .add_system(update_panel.after("Deselect"))
+            .add_system(render_range_ring.after("Deselect"));
+    }
+}
+
+/// Tower the inspection panel is showing
+pub struct Selected(pub Option<Entity>);
+
+#[derive(Component)]
+struct Panel;
+
+#[derive(Component)]
+struct RangeRing;
+
+fn setup(mut commands: Commands, fonts: Res<Fonts>) {
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(5.0),
+                    right: Val::Px(10.0),
+                    ..Rect::default()
+                },
+                ..Style::default()
+            },
+            text: Text::with_section(
+                String::new(),
+                TextStyle {
+                    font: fonts.main.clone(),
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                },
+                TextAlignment::default(),
+            ),
+            ..TextBundle::default()
+        })
+        .insert(Panel);
+}
+
+/// Towers are not pickable themselves, so the grid tells which tower stands where the ground was clicked
+fn select_on_click(
+    mut er: EventReader<OnActionBegin>,
+    mut selected: ResMut<Selected>,
+    grid: Res<Grid>,
+    cameras: Query<&PickingCamera>,
+    ground: Query<(), With<Ground>>,
+    towers: Query<(), With<Tower>>,
+) {
+    for action in er.iter() {
+        if action.action != "LEFT_CLICK" {
+            continue;
+        }
+
+        let camera = cameras.single();
+
+        let (picked_entity, intersection) = if let Some(val) = camera.intersect_top() {
+            val
+        } else {
+            continue;
+        };
+
+        if ground.get(picked_entity).is_err() {
+            continue;
+        }
+
+        let tower = grid
+            .get(Grid::to_grid_pos(intersection.position()))
+            .filter(|entity| towers.get(*entity).is_ok());
+        if selected.0 != tower {
+            selected.0 = tower;
+        }
+    }
+}
+
+/// Towers turned into rocks or despawned by a restart cannot be inspected
+fn deselect_removed(mut selected: ResMut<Selected>, towers: Query<(), With<Tower>>) {
+    if let Some(entity) = selected.0 {
+        if towers.get(entity).is_err() {
+            selected.0 = None;
+        }
+    }
+}
+
+fn cycle_targeting(
+    playback: Option<Res<Playback>>,
+    selected: Res<Selected>,
+    mut er: EventReader<OnActionBegin>,
+    mut ew: EventWriter<SetTargeting>,
+    towers: Query<(&Transform, &TargetingMode), With<Tower>>,
+) {
+    // Replays change targeting themselves
+    if playback.is_some() {
+        return;
+    }
+    for action in er.iter() {
+        if action.action != "CYCLE_TARGETING" {
+            continue;
+        }
+        if let Some((transform, mode)) = selected.0.and_then(|entity| towers.get(entity).ok()) {
+            ew.send(SetTargeting {
+                pos: Grid::to_grid_pos(transform.translation),
+                mode: mode.next(),
+            });
+        }
+    }
+}
+
+/// Rewritten every frame while a tower is selected, its statistics change with every hit
+fn update_panel(
+    selected: Res<Selected>,
+    towers: Query<
+        (
+            &Name,
+            &Damage,
+            &DamageType,
+            &AttackSpeed,
+            Option<&Buffs>,
+            &Range,
+            &TargetingMode,
+            &OnHitAbilities,
+            &Auras,
+            Option<&TowerStats>,
+        ),
+        With<Tower>,
+    >,
+    mut panels: Query<&mut Text, With<Panel>>,
+) {
+    let panel = selected
+        .0
+        .and_then(|entity| towers.get(entity).ok())
+        .map_or_else(String::new, panel_text);
+    for mut text in panels.iter_mut() {
+        if text.sections[0].value != panel {
+            text.sections[0].value = panel.clone();
+        }
+    }
+}
+
+fn panel_text(
+    (
+        name,
+        damage,
+        damage_type,
+        AttackSpeed(speed),
+        buffs,
+        Range(range),
+        targeting,
+        OnHitAbilities(abilities),
+        Auras(auras),
+        stats,
+    ): (
+        &Name,
+        &Damage,
+        &DamageType,
+        &AttackSpeed,
+        Option<&Buffs>,
+        &Range,
+        &TargetingMode,
+        &OnHitAbilities,
+        &Auras,
+        Option<&TowerStats>,
+    ),
+) -> String {
+    let damage = match damage {
+        Damage::Range(range) => format!("{}-{}", range.start(), range.end()),
+        Damage::Fixed(damage) => damage.to_string(),
+    };
+    // Attack speed and range buffs are already added to the tower's own stats
+    let buffs = buffs.copied().unwrap_or_default();
+    let mut text = format!(
+        "{}\nDamage: {}{} {:?}\nAttack speed: {:.2}{}\nRange: {}{}\nTargeting: {:?} (T to change)",
+        name.as_str(),
+        damage,
+        from_auras(buffs.damage > 0, format_args!("+{}%", buffs.damage)),
+        damage_type,
+        speed,
+        from_auras(
+            buffs.attack_speed > 0.0,
+            format_args!("{:+.2}", buffs.attack_speed)
+        ),
+        range,
+        from_auras(buffs.range > 0.0, format_args!("{:+}", buffs.range)),
+        targeting
+    );
+    if buffs.crit_chance > 0 {
+        text.push_str(&format!(
+            "\n{}% chance of critical hits from auras",
+            buffs.crit_chance
+        ));
+    }
+    if buffs.on_hit_chance > 0 {
+        text.push_str(&format!(
+            "\n+{}% on-hit ability chance from auras",
+            buffs.on_hit_chance
+        ));
+    }
+    for ability in abilities {
+        text.push_str(&format!("\nOn hit: {}", ability));
+    }
+    for aura in auras {
+        text.push_str(&format!("\nAura: {}", aura));
+    }
+    let stats = stats.copied().unwrap_or_default();
+    text.push_str(&format!(
+        "\nTotal damage: {}\nKills: {}",
+        stats.damage, stats.kills
+    ));
+    text
+}
+
+fn from_auras(buffed: bool, buff: fmt::Arguments<'_>) -> String {
+    if buffed {
+        format!(" ({} from auras)", buff)
+    } else {
+        String::new()
+    }
+}
+
+/// Redrawn when the selection changes or a range aura changes the selected tower's range
+fn render_range_ring(
+    mut commands: Commands,
+    selected: Res<Selected>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut mats: ResMut<Assets<StandardMaterial>>,
+    towers: Query<(&GlobalTransform, &Range), With<Tower>>,
+    changed: Query<(), (With<Tower>, Changed<Range>)>,
+    rings: Query<Entity, With<RangeRing>>,
+) {
+    let range_changed = selected
+        .0
+        .map_or(false, |entity| changed.get(entity).is_ok());
+    if !selected.is_changed() && !range_changed {
+        return;
+    }
+    for entity in rings.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let (transform, Range(range)) =
+        if let Some(tower) = selected.0.and_then(|entity| towers.get(entity).ok()) {
+            tower
+        } else {
+            return;
+        };
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: meshes.add(
+                shape::Torus {
+                    radius: *range,
+                    ring_radius: 0.05,
+                    subdivisions_segments: 64,
+                    subdivisions_sides: 8,
+                }
+                .into(),
+            ),
+            material: mats.add(Color::WHITE.into()),
+            transform: Transform::from_translation(Vec3::new(
+                transform.translation.x,
+                0.0,
+                transform.translation.z,
+            )),
+            ..PbrBundle::default()
+        })
+        .insert(RangeRing);
+}
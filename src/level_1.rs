@@ -1,6 +1,7 @@
 use self::assets::GameState;
-use crate::{maps::Level, towers::JustBuilt, workarounds::clear_input_events};
+use crate::{maps::Level, towers::JustBuilt};
 use bevy::prelude::{self, *};
+use serde::{Deserialize, Serialize};
 
 pub mod assets;
 pub mod map;
@@ -11,21 +12,35 @@ impl prelude::Plugin for Plugin {
     fn build(&self, app: &mut prelude::App) {
         app.add_state(LevelState::Building)
             .insert_resource(Level(1))
+            .add_event::<Restart>()
             .add_system_set(
                 SystemSet::on_enter(GameState::Play)
-                    .with_system(map::build_ground.after("Build grid"))
                     .with_system(map::build_grid.label("Build grid")),
             )
             .add_system_set(SystemSet::on_update(LevelState::Building).with_system(build_five))
-            .add_system_set(
-                SystemSet::on_enter(LevelState::Choosing).with_system(clear_input_events),
-            )
             .add_system_set(SystemSet::on_update(LevelState::Choosing).with_system(choose_one))
-            .add_system_set(SystemSet::on_exit(LevelState::Spawning).with_system(increment_level));
+            .add_system_set(SystemSet::on_exit(LevelState::Spawning).with_system(increment_level))
+            .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(game_over))
+            .add_system_set(SystemSet::on_update(GameState::GameOver).with_system(restart))
+            .add_system_set(SystemSet::on_exit(GameState::GameOver).with_system(reset_level));
+    }
+}
+
+pub struct RenderPlugin;
+
+impl prelude::Plugin for RenderPlugin {
+    fn build(&self, app: &mut prelude::App) {
+        app.add_system_set(
+            SystemSet::on_enter(GameState::Play).with_system(map::build_ground.after("Build grid")),
+        )
+        .add_system_set(SystemSet::on_exit(GameState::GameOver).with_system(map::despawn_ground));
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+/// Starts a new game once the current one is over
+pub struct Restart;
+
+#[derive(Clone, PartialEq, Eq, Debug, Hash, Deserialize, Serialize)]
 pub enum LevelState {
     Building,
     Choosing,
@@ -36,6 +51,31 @@ fn increment_level(mut level: ResMut<Level>) {
     level.0 += 1;
 }
 
+fn game_over(level: Res<Level>, mut level_state: ResMut<State<LevelState>>) {
+    info!("Game over on level {}, press enter to restart", level.0);
+
+    // Creeps are despawned when the game is over, so the wave has to be ended here instead
+    if *level_state.current() == LevelState::Spawning {
+        level_state
+            .set(LevelState::Building)
+            .map_err(|err| error!("Failed to set level state to Building: {}", err))
+            .ok();
+    }
+}
+
+fn restart(mut game_state: ResMut<State<GameState>>, mut er: EventReader<Restart>) {
+    if er.iter().next().is_some() {
+        game_state
+            .set(GameState::Play)
+            .map_err(|err| error!("Failed to set game state to Play: {}", err))
+            .ok();
+    }
+}
+
+fn reset_level(mut level: ResMut<Level>) {
+    *level = Level(1);
+}
+
 fn build_five(
     mut level_state: ResMut<State<LevelState>>,
     mut gem_count: Local<u32>,
@@ -1,17 +1,47 @@
+use crate::{towers::definitions::TowerDefinitions, waves::WaveDefinitions};
 use bevy::{gltf::Gltf, prelude::*};
 use bevy_asset_loader::AssetCollection;
 
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(watch_for_changes);
+    }
+}
+
 #[derive(Clone, Component, PartialEq, Eq, Debug, Hash)]
 pub enum GameState {
     Loading,
     Play,
+    GameOver,
 }
 
 #[derive(AssetCollection, Component)]
-pub struct Fonts {}
+pub struct Fonts {
+    #[asset(path = "fonts/FiraSans-Bold.ttf")]
+    pub main: Handle<Font>,
+}
 
 #[derive(AssetCollection, Component)]
 pub struct Models {
     #[asset(path = "ps1wall.gltf")]
     pub wall: Handle<Gltf>,
 }
+
+#[derive(AssetCollection, Component)]
+pub struct Definitions {
+    #[asset(path = "gems.towers")]
+    pub towers: Handle<TowerDefinitions>,
+    #[asset(path = "level_1.waves")]
+    pub waves: Handle<WaveDefinitions>,
+    #[asset(folder = "maps")]
+    pub maps: Vec<HandleUntyped>,
+}
+
+/// Reloads assets when their files change, so definitions can be balanced while the game is running
+fn watch_for_changes(ass: Res<AssetServer>) {
+    ass.watch_for_changes()
+        .map_err(|err| error!("Failed to watch assets for changes: {:?}", err))
+        .ok();
+}
@@ -0,0 +1,82 @@
+#![warn(clippy::pedantic)]
+#![allow(clippy::type_complexity)]
+#![allow(clippy::too_many_arguments)]
+#![allow(clippy::too_many_lines)]
+#![allow(clippy::must_use_candidate)]
+#![allow(clippy::needless_pass_by_value)]
+#![allow(clippy::enum_glob_use)]
+
+use bevy::{app::PluginGroupBuilder, prelude::*};
+
+mod abilities;
+pub mod buffs;
+mod camera;
+pub mod cli;
+pub mod creeps;
+pub mod cursor;
+pub mod damage;
+pub mod effects;
+mod grid;
+mod hud;
+mod input;
+mod inspect;
+pub mod level_1;
+pub mod maps;
+pub mod math_utils;
+mod meter;
+pub mod path;
+pub mod player;
+pub mod replay;
+pub mod rng;
+pub mod route;
+pub mod save;
+pub mod simulation;
+pub mod stats;
+pub mod towers;
+pub mod waves;
+pub mod workarounds;
+
+/// Game logic that runs without a window, see `simulation::Simulation`
+pub struct GamePlugins;
+
+impl PluginGroup for GamePlugins {
+    fn build(&mut self, group: &mut PluginGroupBuilder) {
+        group
+            .add(level_1::Plugin)
+            .add(grid::Plugin)
+            .add(towers::Plugin)
+            .add(creeps::Plugin)
+            .add(abilities::Plugin)
+            .add(buffs::Plugin)
+            .add(effects::Plugin)
+            .add(player::Plugin)
+            .add(route::Plugin)
+            .add(rng::Plugin)
+            .add(stats::Plugin);
+    }
+}
+
+/// Everything headless simulations leave out: rendering, input and loading definitions through the asset server
+pub struct FrontendPlugins;
+
+impl PluginGroup for FrontendPlugins {
+    fn build(&mut self, group: &mut PluginGroupBuilder) {
+        group
+            .add(level_1::assets::Plugin)
+            .add(towers::definitions::Plugin)
+            .add(waves::Plugin)
+            .add(maps::Plugin)
+            .add(camera::Plugin)
+            .add(cursor::Plugin)
+            .add(hud::Plugin)
+            .add(inspect::Plugin)
+            .add(meter::Plugin)
+            .add(replay::Plugin)
+            .add(save::Plugin)
+            .add(input::Plugin)
+            .add(level_1::RenderPlugin)
+            .add(towers::RenderPlugin)
+            .add(creeps::RenderPlugin)
+            .add(route::RenderPlugin);
+    }
+}
@@ -11,21 +11,11 @@ use bevy_asset_loader::AssetLoader;
 use bevy_inspector_egui::InspectableRegistry;
 use bevy_mod_picking::{InteractablePickingPlugin, PickingPlugin};
 use kurinji::KurinjiPlugin;
-use level_1::assets::{self, GameState};
-
-mod abilities;
-pub mod buffs;
-mod camera;
-pub mod creeps;
-pub mod cursor;
-mod grid;
-mod input;
-pub mod level_1;
-pub mod maps;
-pub mod math_utils;
-pub mod path;
-pub mod towers;
-pub mod workarounds;
+use rusttd::{
+    cli,
+    level_1::assets::{self, GameState},
+    replay, FrontendPlugins, GamePlugins,
+};
 
 fn main() {
     let mut app = App::new();
@@ -33,9 +23,16 @@ fn main() {
     AssetLoader::new(GameState::Loading, GameState::Play)
         .with_collection::<assets::Fonts>()
         .with_collection::<assets::Models>()
+        .with_collection::<assets::Definitions>()
         .build(&mut app);
 
+    let mut args = cli::Args::parse();
+    if let Some(playback) = replay::prepare(&mut args) {
+        app.insert_resource(playback);
+    }
+
     app.insert_resource(Msaa { samples: 4 })
+        .insert_resource(args)
         .insert_resource(InspectableRegistry::default())
         .insert_resource(ClearColor(Color::BLACK))
         .insert_resource(AmbientLight {
@@ -49,14 +46,7 @@ fn main() {
         .add_plugin(KurinjiPlugin)
         // Internal plugins
         .add_state(GameState::Loading)
-        .add_plugin(level_1::Plugin)
-        .add_plugin(camera::Plugin)
-        .add_plugin(grid::Plugin)
-        .add_plugin(cursor::Plugin)
-        .add_plugin(input::Plugin)
-        .add_plugin(towers::Plugin)
-        .add_plugin(creeps::Plugin)
-        .add_plugin(abilities::Plugin)
-        .add_plugin(buffs::Plugin)
+        .add_plugins(GamePlugins)
+        .add_plugins(FrontendPlugins)
         .run();
 }
@@ -1,4 +1,224 @@
-#[derive(bevy::prelude::Component)]
+use crate::{cli::Args, level_1::assets::GameState};
+use anyhow::bail;
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::{self, *},
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+pub struct Plugin;
+
+impl prelude::Plugin for Plugin {
+    fn build(&self, app: &mut prelude::App) {
+        app.add_asset::<MapDefinition>()
+            .init_asset_loader::<MapDefinitionLoader>()
+            .add_system_set(
+                SystemSet::on_exit(GameState::Loading).with_system(MapDefinition::select),
+            )
+            .add_system(MapDefinition::sync);
+    }
+}
+
+#[derive(Component)]
 pub struct Ground;
 
+/// Marks a tile of the map that cannot be built on
+#[derive(Component)]
+pub struct Tile;
+
 pub struct Level(pub u32);
+
+/// A playable map, loaded from a RON file with the `map` extension in the `maps` asset folder
+#[derive(Clone, Deserialize, TypeUuid)]
+#[uuid = "9a6cbbd4-409f-4803-8d08-70ff0a0a5be6"]
+pub struct MapDefinition {
+    /// Name used to select the map from the command line
+    pub name: String,
+    pub bounds: Bounds,
+    /// Tiles that cannot be built on, creeps can still walk over them
+    pub blocked: Vec<(i32, i32)>,
+    /// Checkpoints every creep visits in order after leaving its spawner
+    pub waypoints: Vec<(i32, i32)>,
+    pub spawners: Vec<(i32, i32)>,
+    /// Tiles that are slower to walk over, creeps prefer routes with a lower total weight
+    #[serde(default)]
+    pub weights: HashMap<(i32, i32), u32>,
+    /// Height flying creeps hover at while flying straight between checkpoints
+    #[serde(default = "default_flying_altitude")]
+    pub flying_altitude: f32,
+    pub style: GroundStyle,
+}
+
+fn default_flying_altitude() -> f32 {
+    2.0
+}
+
+/// Inclusive rectangle of grid positions
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+pub struct Bounds {
+    pub min: (i32, i32),
+    pub max: (i32, i32),
+}
+
+impl Bounds {
+    pub fn contains(&self, pos: (i32, i32)) -> bool {
+        (self.min.0..=self.max.0).contains(&pos.0) && (self.min.1..=self.max.1).contains(&pos.1)
+    }
+}
+
+#[derive(Clone, Deserialize)]
+pub struct GroundStyle {
+    pub ground: Color,
+    pub tiles: Color,
+}
+
+impl MapDefinition {
+    /// Returns the checkpoints creeps from each spawner visit, starting at the spawner itself
+    pub fn routes(&self) -> Vec<Vec<(i32, i32)>> {
+        self.spawners
+            .iter()
+            .map(|spawner| {
+                let mut route = vec![*spawner];
+                route.extend(&self.waypoints);
+                route
+            })
+            .collect()
+    }
+
+    /// Parses and validates a map without going through the asset server
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the RON is malformed or describes an invalid map
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        let map: Self = ron::de::from_bytes(bytes)?;
+        map.validate()?;
+        Ok(map)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.bounds.min.0 > self.bounds.max.0 || self.bounds.min.1 > self.bounds.max.1 {
+            bail!("Map {} has bounds with min larger than max", self.name);
+        }
+        if self.waypoints.is_empty() {
+            bail!("Map {} has no waypoints", self.name);
+        }
+        if self.spawners.is_empty() {
+            bail!("Map {} has no spawners", self.name);
+        }
+        if self.flying_altitude <= 0.0 {
+            bail!("Map {} has a flying altitude below the ground", self.name);
+        }
+        if let Some(pos) = self
+            .weights
+            .iter()
+            .find(|(_, weight)| **weight == 0)
+            .map(|(pos, _)| pos)
+        {
+            bail!(
+                "Map {} has a weight of 0 at {};{}, weights must be at least 1",
+                self.name,
+                pos.0,
+                pos.1
+            );
+        }
+        for pos in self
+            .blocked
+            .iter()
+            .chain(&self.waypoints)
+            .chain(&self.spawners)
+            .chain(self.weights.keys())
+        {
+            if !self.bounds.contains(*pos) {
+                bail!(
+                    "Map {} has position {};{} outside its bounds",
+                    self.name,
+                    pos.0,
+                    pos.1
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Makes the map selected on the command line available as a resource once every map is loaded. An unknown map
+    /// falls back to the default one, listing the maps that could have been selected
+    pub fn select(
+        mut commands: Commands,
+        mut args: ResMut<Args>,
+        assets: Res<Assets<MapDefinition>>,
+    ) {
+        let maps = assets.iter().map(|(_, map)| map).collect::<Vec<_>>();
+        if let Some(map) = maps.iter().find(|map| map.name == args.map) {
+            commands.insert_resource((*map).clone());
+            return;
+        }
+
+        let mut names = maps.iter().map(|map| map.name.as_str()).collect::<Vec<_>>();
+        names.sort_unstable();
+        let default = Args::default().map;
+        let fallback = maps
+            .iter()
+            .find(|map| map.name == default)
+            .or_else(|| maps.first());
+        if let Some(map) = fallback {
+            error!(
+                "No map named {} in assets/maps, playing {} instead. Available maps: {}",
+                args.map,
+                map.name,
+                names.join(", ")
+            );
+            // Hot reloading and saves follow the map actually played
+            args.map = map.name.clone();
+            commands.insert_resource((*map).clone());
+        } else {
+            error!("No maps in assets/maps, there is nothing to play");
+            std::process::exit(1);
+        }
+    }
+
+    /// Keeps the selected map up to date when its file changes
+    pub fn sync(
+        mut commands: Commands,
+        args: Res<Args>,
+        mut er: EventReader<AssetEvent<MapDefinition>>,
+        assets: Res<Assets<MapDefinition>>,
+    ) {
+        for event in er.iter() {
+            match event {
+                AssetEvent::Created { handle } | AssetEvent::Modified { handle } => {
+                    if let Some(map) = assets.get(handle) {
+                        if map.name == args.map {
+                            commands.insert_resource(map.clone());
+                        }
+                    }
+                }
+                AssetEvent::Removed { .. } => {}
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct MapDefinitionLoader;
+
+impl AssetLoader for MapDefinitionLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let map = MapDefinition::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(map));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["map"]
+    }
+}
@@ -0,0 +1,174 @@
+use crate::{
+    level_1::{
+        assets::{Fonts, GameState},
+        LevelState,
+    },
+    stats::{GemTypeStats, TowerStats, WaveStats},
+};
+use bevy::prelude::{self, *};
+use kurinji::OnActionBegin;
+
+/// Damage each tower dealt during the last wave, shown between waves and sorted with the `SORT_METER` action
+pub struct Plugin;
+
+impl prelude::Plugin for Plugin {
+    fn build(&self, app: &mut prelude::App) {
+        app.insert_resource(DamageMeter {
+            visible: false,
+            sort: SortBy::Damage,
+        })
+        .add_system_set(SystemSet::on_exit(GameState::Loading).with_system(setup))
+        .add_system_set(SystemSet::on_enter(LevelState::Building).with_system(show))
+        .add_system_set(SystemSet::on_enter(LevelState::Spawning).with_system(hide))
+        .add_system(cycle_sort)
+        .add_system(update_meter);
+    }
+}
+
+struct DamageMeter {
+    visible: bool,
+    sort: SortBy,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum SortBy {
+    Damage,
+    Kills,
+    Overkill,
+}
+
+impl SortBy {
+    fn next(self) -> Self {
+        match self {
+            SortBy::Damage => SortBy::Kills,
+            SortBy::Kills => SortBy::Overkill,
+            SortBy::Overkill => SortBy::Damage,
+        }
+    }
+
+    /// Highest first
+    fn sort<T>(self, rows: &mut [(T, TowerStats)]) {
+        match self {
+            SortBy::Damage => rows.sort_by(|(_, a), (_, b)| b.damage.cmp(&a.damage)),
+            SortBy::Kills => rows.sort_by(|(_, a), (_, b)| b.kills.cmp(&a.kills)),
+            SortBy::Overkill => rows.sort_by(|(_, a), (_, b)| b.overkill.cmp(&a.overkill)),
+        }
+    }
+}
+
+#[derive(Component)]
+struct MeterText;
+
+fn setup(mut commands: Commands, fonts: Res<Fonts>) {
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    bottom: Val::Px(5.0),
+                    left: Val::Px(10.0),
+                    ..Rect::default()
+                },
+                ..Style::default()
+            },
+            text: Text::with_section(
+                String::new(),
+                TextStyle {
+                    font: fonts.main.clone(),
+                    font_size: 18.0,
+                    color: Color::WHITE,
+                },
+                TextAlignment::default(),
+            ),
+            ..TextBundle::default()
+        })
+        .insert(MeterText);
+}
+
+fn show(mut meter: ResMut<DamageMeter>) {
+    meter.visible = true;
+}
+
+fn hide(mut meter: ResMut<DamageMeter>) {
+    meter.visible = false;
+}
+
+fn cycle_sort(mut er: EventReader<OnActionBegin>, mut meter: ResMut<DamageMeter>) {
+    for action in er.iter() {
+        if action.action == "SORT_METER" {
+            meter.sort = meter.sort.next();
+        }
+    }
+}
+
+/// Towers despawned or turned into rocks since the wave keep their line, without the name they lost. Hits ending a
+/// wave can be recorded after the meter is shown, so it is also redrawn whenever the totals change while visible
+fn update_meter(
+    meter: Res<DamageMeter>,
+    wave: Res<WaveStats>,
+    gem_types: Res<GemTypeStats>,
+    names: Query<&Name>,
+    mut texts: Query<&mut Text, With<MeterText>>,
+) {
+    let totals_changed = wave.is_changed() || gem_types.is_changed();
+    if !meter.is_changed() && !(meter.visible && totals_changed) {
+        return;
+    }
+
+    let content = if meter.visible && !wave.towers.is_empty() {
+        let total = wave.towers.values().map(|stats| stats.damage).sum::<u64>();
+        let mut towers = wave
+            .towers
+            .iter()
+            .map(|(entity, stats)| {
+                let name = names.get(*entity).map_or_else(
+                    |_| "Removed tower".to_string(),
+                    |name| name.as_str().to_string(),
+                );
+                (name, *stats)
+            })
+            .collect::<Vec<_>>();
+        meter.sort.sort(&mut towers);
+
+        let mut content = format!("Damage last wave, sorted by {:?}", meter.sort);
+        for (name, stats) in towers {
+            content.push_str(&format!(
+                "\n{}: {} ({}%), {} kills, {} overkill",
+                name,
+                stats.damage,
+                percentage(stats.damage, total),
+                stats.kills,
+                stats.overkill
+            ));
+        }
+
+        let mut gem_types = gem_types
+            .0
+            .iter()
+            .map(|(r#type, stats)| (*r#type, *stats))
+            .collect::<Vec<_>>();
+        meter.sort.sort(&mut gem_types);
+        content.push_str("\n\nDamage this game by gem type");
+        for (r#type, stats) in gem_types {
+            content.push_str(&format!(
+                "\n{:?}: {}, {} kills",
+                r#type, stats.damage, stats.kills
+            ));
+        }
+        content
+    } else {
+        String::new()
+    };
+
+    for mut text in texts.iter_mut() {
+        text.sections[0].value = content.clone();
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn percentage(part: u64, total: u64) -> String {
+    if total == 0 {
+        return "0".to_string();
+    }
+    format!("{:.0}", part as f64 / total as f64 * 100.0)
+}
@@ -1,25 +1,154 @@
 use crate::grid::Grid;
-use pathfinding::prelude::bfs;
+use pathfinding::prelude::astar;
 
 pub fn resolve(grid: &Grid, goals: &[(i32, i32)]) -> Option<Vec<(i32, i32)>> {
-    let mut res = vec![];
+    resolve_with_blocked(grid, goals, &[])
+}
+
+/// Resolves a route as if the given positions were also occupied
+pub fn resolve_with_blocked(
+    grid: &Grid,
+    goals: &[(i32, i32)],
+    blocked: &[(i32, i32)],
+) -> Option<Vec<(i32, i32)>> {
+    let mut res: Vec<(i32, i32)> = vec![];
     for i in 1..goals.len() {
         let prev_goal = goals[i - 1];
         let goal = goals[i];
-        let to_add = bfs(
+        let (mut to_add, _cost) = astar(
             &prev_goal,
             |start| {
-                let mut succ = Grid::neighbours(*start);
-                succ.retain(|goal| match goal {
-                    // FIXME: map borders exist to avoid infinite search.
-                    (-100..=100, -100..=100) => grid.get(*goal).is_none(),
-                    _ => false,
-                });
-                succ
+                Grid::neighbours(*start)
+                    .into_iter()
+                    .filter(|pos| grid.walkable(*pos) && !blocked.contains(pos))
+                    .map(|pos| (pos, grid.weight(pos)))
+                    .collect::<Vec<_>>()
             },
+            |start| distance(*start, goal),
             |start| *start == goal,
-        );
-        res.append(&mut to_add?);
+        )?;
+        // Each segment starts where the previous one ended
+        if !res.is_empty() {
+            to_add.remove(0);
+        }
+        res.append(&mut to_add);
     }
     Some(res)
 }
+
+/// Checks if occupying the given positions would leave any of the routes without a path
+pub fn would_block(grid: &Grid, routes: &[Vec<(i32, i32)>], positions: &[(i32, i32)]) -> bool {
+    routes.iter().any(|route| {
+        route
+            .iter()
+            .any(|checkpoint| positions.contains(checkpoint))
+            || resolve_with_blocked(grid, route, positions).is_none()
+    })
+}
+
+/// Manhattan distance, never overestimates since every step costs at least 1
+fn distance(a: (i32, i32), b: (i32, i32)) -> u32 {
+    (a.0 - b.0).unsigned_abs() + (a.1 - b.1).unsigned_abs()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::maps::Bounds;
+    use bevy::prelude::Entity;
+
+    fn grid(width: i32, height: i32) -> Grid {
+        Grid::new(Bounds {
+            min: (0, 0),
+            max: (width - 1, height - 1),
+        })
+    }
+
+    fn wall(grid: &mut Grid, positions: &[(i32, i32)]) {
+        grid.add_building(positions, Entity::new(0)).unwrap();
+    }
+
+    fn assert_connected(path: &[(i32, i32)]) {
+        for step in path.windows(2) {
+            assert_eq!(distance(step[0], step[1]), 1, "{:?} is not a step", step);
+        }
+    }
+
+    #[test]
+    fn straight_line() {
+        let path = resolve(&grid(5, 5), &[(0, 0), (4, 0)]).unwrap();
+        assert_eq!(path, vec![(0, 0), (1, 0), (2, 0), (3, 0), (4, 0)]);
+    }
+
+    #[test]
+    fn maze() {
+        // Two walls with gaps on opposite sides force a zig-zag
+        let mut grid = grid(5, 5);
+        wall(&mut grid, &[(1, 0), (1, 1), (1, 2), (1, 3)]);
+        wall(&mut grid, &[(3, 1), (3, 2), (3, 3), (3, 4)]);
+        let path = resolve(&grid, &[(0, 0), (4, 4)]).unwrap();
+        assert_connected(&path);
+        assert_eq!(path.first(), Some(&(0, 0)));
+        assert_eq!(path.last(), Some(&(4, 4)));
+        assert!(path.contains(&(1, 4)));
+        assert!(path.contains(&(3, 0)));
+        assert_eq!(path.len(), 17);
+    }
+
+    #[test]
+    fn blocked_tiles_are_walkable() {
+        let mut grid = grid(3, 1);
+        grid.block((1, 0)).unwrap();
+        let path = resolve(&grid, &[(0, 0), (2, 0)]).unwrap();
+        assert_eq!(path, vec![(0, 0), (1, 0), (2, 0)]);
+    }
+
+    #[test]
+    fn unreachable_goal() {
+        let mut grid = grid(5, 5);
+        wall(&mut grid, &[(2, 0), (2, 1), (2, 2), (2, 3), (2, 4)]);
+        assert_eq!(resolve(&grid, &[(0, 0), (4, 4)]), None);
+    }
+
+    #[test]
+    fn goal_outside_bounds() {
+        assert_eq!(resolve(&grid(5, 5), &[(0, 0), (5, 0)]), None);
+    }
+
+    #[test]
+    fn waypoint_chain() {
+        let waypoints = [(0, 0), (4, 0), (4, 4), (0, 4)];
+        let path = resolve(&grid(5, 5), &waypoints).unwrap();
+        assert_connected(&path);
+        assert_eq!(path.len(), 13);
+        let visits: Vec<_> = waypoints
+            .iter()
+            .map(|waypoint| path.iter().position(|pos| pos == waypoint).unwrap())
+            .collect();
+        assert!(visits.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn unreachable_waypoint() {
+        let mut grid = grid(5, 5);
+        wall(&mut grid, &[(3, 4), (4, 3)]);
+        assert_eq!(resolve(&grid, &[(0, 0), (2, 2), (4, 4)]), None);
+    }
+
+    #[test]
+    fn avoids_heavy_tiles() {
+        let mut grid = grid(3, 2);
+        grid.set_weight((1, 0), 5);
+        let path = resolve(&grid, &[(0, 0), (2, 0)]).unwrap();
+        assert_eq!(path, vec![(0, 0), (0, 1), (1, 1), (2, 1), (2, 0)]);
+    }
+
+    #[test]
+    fn would_block_corridor() {
+        let grid = grid(3, 1);
+        let routes = vec![vec![(0, 0), (2, 0)]];
+        assert!(would_block(&grid, &routes, &[(1, 0)]));
+        assert!(would_block(&grid, &routes, &[(2, 0)]));
+        assert!(!would_block(&grid, &routes, &[]));
+    }
+}
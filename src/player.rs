@@ -0,0 +1,90 @@
+use crate::{
+    level_1::{assets::GameState, LevelState},
+    towers::MAX_QUALITY_LEVEL,
+};
+use bevy::prelude::{self, *};
+
+// Lives the player starts every game with
+pub const STARTING_HEALTH: u32 = 20;
+
+pub struct Plugin;
+
+impl prelude::Plugin for Plugin {
+    fn build(&self, app: &mut prelude::App) {
+        app.insert_resource(PlayerHealth::default())
+            .insert_resource(Gold::default())
+            .insert_resource(QualityLevel::default())
+            .add_event::<UpgradeQuality>()
+            .add_system_set(SystemSet::on_update(LevelState::Building).with_system(upgrade_quality))
+            .add_system_set(SystemSet::on_exit(GameState::GameOver).with_system(reset));
+    }
+}
+
+pub struct PlayerHealth(pub u32);
+
+impl Default for PlayerHealth {
+    fn default() -> Self {
+        Self(STARTING_HEALTH)
+    }
+}
+
+impl PlayerHealth {
+    /// Reduces health by the given amount, returns true if the player has run out of health
+    pub fn damage(&mut self, amount: u32) -> bool {
+        self.0 = self.0.saturating_sub(amount);
+        self.0 == 0
+    }
+}
+
+#[derive(Default)]
+pub struct Gold(pub u32);
+
+/// Purchasable level raising the odds of rolling higher gem qualities, see `towers::QualityDistribution`
+#[derive(Default)]
+pub struct QualityLevel(pub u32);
+
+impl QualityLevel {
+    /// Gold needed to purchase the next quality level
+    pub fn cost(&self) -> u32 {
+        20 + 30 * self.0
+    }
+}
+
+/// Purchases the next quality level if the player has enough gold
+pub struct UpgradeQuality;
+
+fn upgrade_quality(
+    mut er: EventReader<UpgradeQuality>,
+    mut gold: ResMut<Gold>,
+    mut quality_level: ResMut<QualityLevel>,
+) {
+    for UpgradeQuality in er.iter() {
+        if quality_level.0 >= MAX_QUALITY_LEVEL {
+            info!("Quality level is already at its maximum");
+            continue;
+        }
+
+        let cost = quality_level.cost();
+        if gold.0 < cost {
+            info!(
+                "Upgrading quality costs {} gold, only {} available",
+                cost, gold.0
+            );
+            continue;
+        }
+
+        gold.0 -= cost;
+        quality_level.0 += 1;
+        info!("Upgraded quality level to {}", quality_level.0);
+    }
+}
+
+fn reset(
+    mut health: ResMut<PlayerHealth>,
+    mut gold: ResMut<Gold>,
+    mut quality_level: ResMut<QualityLevel>,
+) {
+    *health = PlayerHealth::default();
+    *gold = Gold::default();
+    *quality_level = QualityLevel::default();
+}
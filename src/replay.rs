@@ -0,0 +1,400 @@
+use crate::{
+    cli::Args,
+    level_1::{assets::GameState, LevelState},
+    maps::Level,
+    player::UpgradeQuality,
+    rng::{CombatRng, GemRng, Seed},
+    save::{read_ron, write_ron, GameLoaded},
+    simulation::STEP,
+    towers::{
+        recipes::CombineRecipe,
+        targeting::{SetTargeting, TargetingMode},
+        BuildGem, ChooseGem, CombineGem,
+    },
+};
+use bevy::prelude::{self, *};
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, path::Path};
+
+/// Records the player's inputs of every game, and plays back a replay given with `--replay` instead of reading the
+/// mouse. While recording or playing back, waves advance by a fixed `simulation::STEP` every frame and inputs are
+/// played back on the frame they were made, so a replay plays out the same on every machine. Slow machines play waves
+/// in slow motion instead.
+pub struct Plugin;
+
+impl prelude::Plugin for Plugin {
+    fn build(&self, app: &mut prelude::App) {
+        app.init_resource::<Progress>()
+            .add_system_to_stage(CoreStage::First, fixed_step)
+            .add_system_to_stage(CoreStage::First, track_progress)
+            .add_system_set(SystemSet::on_exit(GameState::Loading).with_system(start_recording))
+            .add_system(record)
+            .add_system(playback)
+            .add_system(stop_recording)
+            .add_system_set(SystemSet::on_enter(LevelState::Spawning).with_system(save))
+            .add_system_set(
+                SystemSet::on_enter(GameState::GameOver)
+                    .with_system(save.label("Save replay"))
+                    .with_system(stop_playback.after("Save replay")),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::GameOver).with_system(start_recording));
+    }
+}
+
+/// Everything needed to replay a game, saved as RON
+#[derive(Deserialize, Serialize)]
+pub struct Replay {
+    pub map: String,
+    pub seed: u64,
+    pub events: VecDeque<ReplayEvent>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct ReplayEvent {
+    pub level: u32,
+    /// The input is held back during playback until the game gets to this state
+    pub state: LevelState,
+    /// Frames since the game got to `level` and `state`
+    pub frame: u64,
+    /// Where the random number streams were when the game got to `level` and `state`, playback stops if they differ
+    #[serde(default)]
+    pub checkpoint: Option<Checkpoint>,
+    pub input: PlayerInput,
+}
+
+impl ReplayEvent {
+    fn same_frame(&self, other: &Self) -> bool {
+        self.level == other.level && self.state == other.state && self.frame == other.frame
+    }
+}
+
+/// Positions of the random number streams, see `GemRng::position`
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub struct Checkpoint {
+    pub gem_rng: u64,
+    pub combat_rng: u64,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub enum PlayerInput {
+    Build((i32, i32)),
+    Choose((i32, i32)),
+    Combine((i32, i32)),
+    Recipe((i32, i32)),
+    UpgradeQuality,
+    Targeting((i32, i32), TargetingMode),
+}
+
+impl Replay {
+    /// # Errors
+    ///
+    /// Will return `Err` if the file cannot be read or is not a replay
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        read_ron(path.as_ref())
+    }
+
+    /// # Errors
+    ///
+    /// Will return `Err` if the file cannot be written
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        write_ron(self, path.as_ref())
+    }
+}
+
+/// Replay being played back, player input is ignored until it runs out
+pub struct Playback(pub Replay);
+
+/// Loads the replay given on the command line, which decides the map and seed of the game
+pub fn prepare(args: &mut Args) -> Option<Playback> {
+    let path = args.replay.as_ref()?;
+    match Replay::load(path) {
+        Ok(replay) => {
+            args.map = replay.map.clone();
+            args.seed = Some(replay.seed);
+            Some(Playback(replay))
+        }
+        Err(err) => {
+            eprintln!("Failed to load replay: {:?}", err);
+            None
+        }
+    }
+}
+
+/// Replay of the game being played
+struct Recording(Replay);
+
+/// Frames the game has spent on its current level and state and where the random number streams were when it got
+/// there. Kept at the start of every frame, so recording and playback count frames the same way
+#[derive(Default)]
+struct Progress {
+    level: u32,
+    state: Option<LevelState>,
+    frames: u64,
+    checkpoint: Checkpoint,
+}
+
+impl Progress {
+    fn is_at(&self, level: u32, state: &LevelState) -> bool {
+        self.level == level && self.state.as_ref() == Some(state)
+    }
+}
+
+fn track_progress(
+    mut progress: ResMut<Progress>,
+    level: Res<Level>,
+    level_state: Res<State<LevelState>>,
+    gem_rng: Res<GemRng>,
+    combat_rng: Res<CombatRng>,
+) {
+    if progress.is_at(level.0, level_state.current()) {
+        progress.frames += 1;
+        return;
+    }
+    *progress = Progress {
+        level: level.0,
+        state: Some(level_state.current().clone()),
+        frames: 0,
+        checkpoint: Checkpoint {
+            gem_rng: gem_rng.position(),
+            combat_rng: combat_rng.position(),
+        },
+    };
+}
+
+/// Advances waves by exactly `STEP` while recording or playing back, whatever the frame took. Runs after Bevy's time
+/// system, which is exclusive and so runs first in the stage
+fn fixed_step(
+    mut time: ResMut<Time>,
+    level_state: Res<State<LevelState>>,
+    recording: Option<Res<Recording>>,
+    playback: Option<Res<Playback>>,
+) {
+    if *level_state.current() != LevelState::Spawning || (recording.is_none() && playback.is_none())
+    {
+        return;
+    }
+    if let Some(last_update) = time.last_update() {
+        time.update_with_instant(last_update + STEP);
+    }
+}
+
+fn start_recording(mut commands: Commands, args: Res<Args>, seed: Res<Seed>) {
+    commands.insert_resource(Recording(Replay {
+        map: args.map.clone(),
+        seed: seed.0,
+        events: VecDeque::new(),
+    }));
+    // Restarting may well get back to the level and state the last game ended on
+    commands.insert_resource(Progress::default());
+}
+
+fn record(
+    level: Res<Level>,
+    level_state: Res<State<LevelState>>,
+    progress: Res<Progress>,
+    recording: Option<ResMut<Recording>>,
+    mut build: EventReader<BuildGem>,
+    mut choose: EventReader<ChooseGem>,
+    mut combine: EventReader<CombineGem>,
+    mut recipe: EventReader<CombineRecipe>,
+    mut upgrade_quality: EventReader<UpgradeQuality>,
+    mut targeting: EventReader<SetTargeting>,
+) {
+    let mut recording = if let Some(recording) = recording {
+        recording
+    } else {
+        return;
+    };
+
+    let inputs = build
+        .iter()
+        .map(|BuildGem { pos }| PlayerInput::Build(*pos))
+        .chain(
+            choose
+                .iter()
+                .map(|ChooseGem { pos }| PlayerInput::Choose(*pos)),
+        )
+        .chain(
+            combine
+                .iter()
+                .map(|CombineGem { pos }| PlayerInput::Combine(*pos)),
+        )
+        .chain(
+            recipe
+                .iter()
+                .map(|CombineRecipe { pos }| PlayerInput::Recipe(*pos)),
+        )
+        .chain(upgrade_quality.iter().map(|_| PlayerInput::UpgradeQuality))
+        .chain(
+            targeting
+                .iter()
+                .map(|SetTargeting { pos, mode }| PlayerInput::Targeting(*pos, *mode)),
+        )
+        .collect::<Vec<_>>();
+    // Inputs made on the frame the game got to a new state are played back on its first frame, unchecked
+    let (frame, checkpoint) = if progress.is_at(level.0, level_state.current()) {
+        (progress.frames, Some(progress.checkpoint))
+    } else {
+        (0, None)
+    };
+    for input in inputs {
+        recording.0.events.push_back(ReplayEvent {
+            level: level.0,
+            state: level_state.current().clone(),
+            frame,
+            checkpoint,
+            input,
+        });
+    }
+}
+
+/// Replays start from a new game, so inputs made after loading a save could never be played back
+fn stop_recording(mut commands: Commands, mut er: EventReader<GameLoaded>) {
+    if er.iter().next().is_some() {
+        info!("Stopped recording the replay after loading a save");
+        commands.remove_resource::<Recording>();
+    }
+}
+
+fn save(args: Res<Args>, recording: Option<Res<Recording>>, playback: Option<Res<Playback>>) {
+    // The replay being played back may well be the file recorded to
+    if playback.is_some() {
+        return;
+    }
+    if let Some(recording) = recording {
+        recording
+            .0
+            .save(&args.record)
+            .map_err(|err| error!("Failed to save replay: {:?}", err))
+            .ok();
+    }
+}
+
+/// Sends the inputs of the replay once the game has reached their level, state and frame, stopping if the random
+/// number streams are not where they were when recording
+fn playback(
+    mut commands: Commands,
+    level: Res<Level>,
+    progress: Res<Progress>,
+    playback: Option<ResMut<Playback>>,
+    mut build: EventWriter<BuildGem>,
+    mut choose: EventWriter<ChooseGem>,
+    mut combine: EventWriter<CombineGem>,
+    mut recipe: EventWriter<CombineRecipe>,
+    mut upgrade_quality: EventWriter<UpgradeQuality>,
+    mut targeting: EventWriter<SetTargeting>,
+) {
+    let mut playback = if let Some(playback) = playback {
+        playback
+    } else {
+        return;
+    };
+
+    let next = if let Some(next) = playback.0.events.front() {
+        next.clone()
+    } else {
+        info!("Replay finished, the game is yours");
+        commands.remove_resource::<Playback>();
+        return;
+    };
+    // Inputs the game has moved past, like choosing after a wave that ended sooner than when recording, never come up again
+    if next.level < level.0 {
+        playback.0.events.pop_front();
+        return;
+    }
+    if !progress.is_at(next.level, &next.state) || progress.frames < next.frame {
+        return;
+    }
+    if let Some(checkpoint) = next.checkpoint {
+        if checkpoint != progress.checkpoint {
+            error!(
+                "Replay desynced on level {}, the random number streams are at {:?} instead of {:?}, the game is yours",
+                next.level, progress.checkpoint, checkpoint
+            );
+            commands.remove_resource::<Playback>();
+            return;
+        }
+    }
+
+    while let Some(event) = playback
+        .0
+        .events
+        .front()
+        .filter(|event| event.same_frame(&next))
+        .cloned()
+    {
+        match event.input {
+            PlayerInput::Build(pos) => build.send(BuildGem { pos }),
+            PlayerInput::Choose(pos) => choose.send(ChooseGem { pos }),
+            PlayerInput::Combine(pos) => combine.send(CombineGem { pos }),
+            PlayerInput::Recipe(pos) => recipe.send(CombineRecipe { pos }),
+            PlayerInput::UpgradeQuality => upgrade_quality.send(UpgradeQuality),
+            PlayerInput::Targeting(pos, mode) => targeting.send(SetTargeting { pos, mode }),
+        }
+        playback.0.events.pop_front();
+    }
+}
+
+fn stop_playback(mut commands: Commands, playback: Option<Res<Playback>>) {
+    if playback.is_some() {
+        info!("Replay ended with the game over");
+        commands.remove_resource::<Playback>();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::simulation::{Outcome, Simulation};
+
+    /// Plays the first level from its inputs the way `playback` does, each input waits for its state
+    fn play(inputs: &[(LevelState, PlayerInput)]) -> (Outcome, Checkpoint) {
+        let mut simulation = Simulation::load(
+            "assets/maps/level_1.map",
+            "assets/gems.towers",
+            "assets/level_1.waves",
+        )
+        .unwrap();
+        simulation.set_seed(42);
+        for (state, input) in inputs {
+            while simulation
+                .world()
+                .get_resource::<State<LevelState>>()
+                .unwrap()
+                .current()
+                != state
+            {
+                simulation.update();
+            }
+            match *input {
+                PlayerInput::Build(pos) => simulation.send(BuildGem { pos }),
+                PlayerInput::Choose(pos) => simulation.send(ChooseGem { pos }),
+                input => unimplemented!("{:?} is not played in tests", input),
+            }
+            simulation.update();
+        }
+        let outcome = simulation.finish_wave();
+
+        let world = simulation.world();
+        let checkpoint = Checkpoint {
+            gem_rng: world.get_resource::<GemRng>().unwrap().position(),
+            combat_rng: world.get_resource::<CombatRng>().unwrap().position(),
+        };
+        (outcome, checkpoint)
+    }
+
+    #[test]
+    fn replays_reproduce_the_game() {
+        let mut inputs = [(-16, 4), (-14, 4), (-12, 4), (-10, 4), (-8, 4)]
+            .iter()
+            .map(|pos| (LevelState::Building, PlayerInput::Build(*pos)))
+            .collect::<Vec<_>>();
+        inputs.push((LevelState::Choosing, PlayerInput::Choose((-12, 4))));
+
+        let (outcome, checkpoint) = play(&inputs);
+        assert_ne!(outcome, Outcome::TimedOut);
+        // Revealing the gems rolled their types and qualities
+        assert!(checkpoint.gem_rng > 0);
+        assert_eq!(play(&inputs), (outcome, checkpoint));
+    }
+}
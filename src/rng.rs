@@ -0,0 +1,127 @@
+use crate::{cli::Args, level_1::assets::GameState};
+use bevy::prelude::{self, *};
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+use std::convert::TryFrom;
+
+pub struct Plugin;
+
+impl prelude::Plugin for Plugin {
+    fn build(&self, app: &mut prelude::App) {
+        let seed = app
+            .world
+            .get_resource::<Args>()
+            .and_then(|args| args.seed)
+            .map_or_else(Seed::default, Seed);
+        app.insert_resource(seed.gem_rng())
+            .insert_resource(seed.combat_rng())
+            .insert_resource(seed)
+            .add_system_set(SystemSet::on_exit(GameState::GameOver).with_system(reseed));
+    }
+}
+
+// Mixed into the seed so every subsystem draws from its own stream, rolling an extra gem must not change combat
+const GEM_STREAM: u64 = 0x9e37_79b9_7f4a_7c15;
+const COMBAT_STREAM: u64 = 0xc2b2_ae3d_27d4_eb4f;
+
+/// Seed every random roll of a game derives from, given with `--seed` or picked at random
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Seed(pub u64);
+
+impl Default for Seed {
+    fn default() -> Self {
+        Self(rand::random())
+    }
+}
+
+impl Seed {
+    pub fn gem_rng(self) -> GemRng {
+        self.gem_rng_at(0)
+    }
+
+    pub fn combat_rng(self) -> CombatRng {
+        self.combat_rng_at(0)
+    }
+
+    /// The gem stream continued from `position`, see `GemRng::position`
+    pub fn gem_rng_at(self, position: u64) -> GemRng {
+        GemRng(stream_at(self.0 ^ GEM_STREAM, position))
+    }
+
+    /// The combat stream continued from `position`, see `CombatRng::position`
+    pub fn combat_rng_at(self, position: u64) -> CombatRng {
+        CombatRng(stream_at(self.0 ^ COMBAT_STREAM, position))
+    }
+}
+
+/// Random numbers for gem types and qualities revealed after building
+pub struct GemRng(pub ChaCha12Rng);
+
+impl GemRng {
+    /// How far the stream has been drawn from, reading it does not change later rolls
+    pub fn position(&self) -> u64 {
+        position(&self.0)
+    }
+}
+
+/// Random numbers for combat rolls such as damage ranges and critical hits
+pub struct CombatRng(pub ChaCha12Rng);
+
+impl CombatRng {
+    /// How far the stream has been drawn from, reading it does not change later rolls
+    pub fn position(&self) -> u64 {
+        position(&self.0)
+    }
+}
+
+fn stream_at(seed: u64, position: u64) -> ChaCha12Rng {
+    let mut rng = ChaCha12Rng::seed_from_u64(seed);
+    rng.set_word_pos(u128::from(position));
+    rng
+}
+
+// Counted in 32-bit words, a game draws nowhere near `u64::MAX` of them
+fn position(rng: &ChaCha12Rng) -> u64 {
+    u64::try_from(rng.get_word_pos()).unwrap_or(u64::MAX)
+}
+
+/// Restarting replays the same seed
+fn reseed(mut commands: Commands, seed: Res<Seed>) {
+    commands.insert_resource(seed.gem_rng());
+    commands.insert_resource(seed.combat_rng());
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn same_seed_same_rolls() {
+        let (mut a, mut b) = (Seed(42).gem_rng(), Seed(42).gem_rng());
+        for _ in 0..10 {
+            assert_eq!(a.0.gen::<u64>(), b.0.gen::<u64>());
+        }
+    }
+
+    #[test]
+    fn streams_differ() {
+        let seed = Seed(42);
+        assert_ne!(
+            seed.gem_rng().0.gen::<u64>(),
+            seed.combat_rng().0.gen::<u64>()
+        );
+    }
+
+    #[test]
+    fn position_continues_the_stream() {
+        let mut played = Seed(42).combat_rng();
+        for _ in 0..5 {
+            played.0.gen::<u64>();
+        }
+        let mut restored = Seed(42).combat_rng_at(played.position());
+        for _ in 0..10 {
+            assert_eq!(played.0.gen::<u64>(), restored.0.gen::<u64>());
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use crate::{grid::Grid, level_1::LevelState, maps::MapDefinition, path::resolve};
+use bevy::prelude::{self, shape::Plane, *};
+use kurinji::OnActionBegin;
+
+pub struct Plugin;
+
+impl prelude::Plugin for Plugin {
+    fn build(&self, app: &mut prelude::App) {
+        app.insert_resource(CurrentRoute::default())
+            .add_system(update_route.label("Update route"));
+    }
+}
+
+pub struct RenderPlugin;
+
+impl prelude::Plugin for RenderPlugin {
+    fn build(&self, app: &mut prelude::App) {
+        app.insert_resource(RouteDisplay(false))
+            .add_system(toggle_display)
+            .add_system(render_route.after("Update route"));
+    }
+}
+
+/// Paths ground creeps walk for each route of the map, in the same order as `MapDefinition::routes`
+#[derive(Default)]
+pub struct CurrentRoute {
+    paths: Vec<Option<Vec<(i32, i32)>>>,
+    generation: u32,
+}
+
+impl CurrentRoute {
+    /// Returns `None` when the route has no path, which building should never allow
+    pub fn get(&self, route: usize) -> Option<&Vec<(i32, i32)>> {
+        self.paths.get(route)?.as_ref()
+    }
+}
+
+/// Recomputes paths whenever the grid changes. Slots only get occupied between resets, so a path that
+/// is still walkable is still the shortest one and only paths crossing newly occupied slots are resolved again
+fn update_route(
+    mut current: ResMut<CurrentRoute>,
+    grid: Res<Grid>,
+    map: Option<Res<MapDefinition>>,
+    level_state: Res<State<LevelState>>,
+) {
+    let map = if let Some(map) = map {
+        map
+    } else {
+        return;
+    };
+    // Every creep of a wave walks the same route, nothing is built while they do
+    if *level_state.current() == LevelState::Spawning {
+        return;
+    }
+    if !grid.is_changed() && !map.is_changed() {
+        return;
+    }
+
+    let routes = map.routes();
+    if map.is_changed()
+        || current.generation != grid.generation()
+        || current.paths.len() != routes.len()
+    {
+        current.paths = routes.iter().map(|route| resolve(&*grid, route)).collect();
+        current.generation = grid.generation();
+        return;
+    }
+
+    let outdated: Vec<usize> = current
+        .paths
+        .iter()
+        .enumerate()
+        .filter(|(_, path)| match path {
+            Some(path) => path.iter().any(|pos| !grid.walkable(*pos)),
+            None => false,
+        })
+        .map(|(i, _)| i)
+        .collect();
+    for i in outdated {
+        current.paths[i] = resolve(&*grid, &routes[i]);
+    }
+}
+
+/// Debug overlay drawing the current route on the ground
+struct RouteDisplay(bool);
+
+#[derive(Component)]
+struct RouteMarker;
+
+fn toggle_display(mut er: EventReader<OnActionBegin>, mut display: ResMut<RouteDisplay>) {
+    for action in er.iter() {
+        if action.action == "TOGGLE_ROUTE" {
+            display.0 = !display.0;
+        }
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn render_route(
+    mut commands: Commands,
+    display: Res<RouteDisplay>,
+    current: Res<CurrentRoute>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut mats: ResMut<Assets<StandardMaterial>>,
+    markers: Query<Entity, With<RouteMarker>>,
+) {
+    if !display.is_changed() && !current.is_changed() {
+        return;
+    }
+    for entity in markers.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    if !display.0 {
+        return;
+    }
+
+    let mesh = meshes.add(Plane { size: 0.3 }.into());
+    let material = mats.add(Color::YELLOW.into());
+    for path in current.paths.iter().flatten() {
+        for (x, y) in path {
+            commands
+                .spawn_bundle(PbrBundle {
+                    mesh: mesh.clone(),
+                    material: material.clone(),
+                    transform: Transform::from_translation(Vec3::new(*x as f32, -0.04, *y as f32)),
+                    ..PbrBundle::default()
+                })
+                .insert(RouteMarker);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        simulation::Simulation,
+        towers::{footprint, Gem, GemQuality, GemType},
+    };
+
+    fn route(simulation: &Simulation) -> Vec<(i32, i32)> {
+        let current = simulation.world().get_resource::<CurrentRoute>().unwrap();
+        current.get(0).unwrap().clone()
+    }
+
+    #[test]
+    fn recomputed_when_the_grid_changes() {
+        let mut simulation = Simulation::load(
+            "assets/maps/level_1.map",
+            "assets/gems.towers",
+            "assets/level_1.waves",
+        )
+        .unwrap();
+        let gem = Gem {
+            quality: GemQuality::Chipped,
+            r#type: GemType::Ruby,
+        };
+        let before = route(&simulation);
+        let pos = *before.iter().find(|pos| pos.0 == -10).unwrap();
+        simulation.place_gem(pos, gem).unwrap();
+        simulation.update();
+        let after = route(&simulation);
+        assert_ne!(after, before);
+        assert!(footprint(pos).iter().all(|pos| !after.contains(pos)));
+
+        // Creeps keep the route they spawned with
+        simulation
+            .world_mut()
+            .get_resource_mut::<State<LevelState>>()
+            .unwrap()
+            .set(LevelState::Spawning)
+            .unwrap();
+        simulation.update();
+        let pos = *after.iter().find(|pos| pos.0 == -14).unwrap();
+        simulation.place_gem(pos, gem).unwrap();
+        simulation.update();
+        assert_eq!(route(&simulation), after);
+    }
+}
@@ -0,0 +1,394 @@
+use crate::{
+    cli::Args,
+    grid::Grid,
+    level_1::{assets::GameState, map::fill_grid, LevelState},
+    maps::{Level, MapDefinition},
+    player::{Gold, PlayerHealth, QualityLevel},
+    replay::Playback,
+    rng::{CombatRng, GemRng, Seed},
+    simulation::{LayoutTower, PlacedTower},
+    stats::{GemTypeStats, TowerStats},
+    towers::{
+        definitions::TowerDefinitions, footprint, grid_transform, special::Special,
+        targeting::TargetingMode, Gem, JustBuilt, Rock, Tower,
+    },
+};
+use anyhow::{bail, Context};
+use bevy::prelude::{self, *};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{fs, path::Path};
+
+/// Saves and loads the game in progress, only between waves while nothing is waiting to be chosen
+pub struct Plugin;
+
+impl prelude::Plugin for Plugin {
+    fn build(&self, app: &mut prelude::App) {
+        app.add_event::<SaveGame>()
+            .add_event::<LoadGame>()
+            .add_event::<GameLoaded>()
+            .add_system_set(
+                SystemSet::on_update(LevelState::Building)
+                    .with_system(save)
+                    .with_system(load),
+            );
+    }
+}
+
+pub struct SaveGame;
+
+pub struct LoadGame;
+
+/// Sent once a save has been loaded, the game no longer follows from its seed alone
+pub struct GameLoaded;
+
+/// A game in progress, saved as RON. The grid is not saved as such, it is rebuilt from the map, towers and rocks, and
+/// neither are buffs, which are recomputed from the auras. Saves are only made while building, so the level state is
+/// always `LevelState::Building` on load
+#[derive(Deserialize, Serialize)]
+pub struct SaveFile {
+    pub map: String,
+    pub seed: u64,
+    pub level: u32,
+    pub health: u32,
+    pub gold: u32,
+    pub quality_level: u32,
+    /// Positions the random number streams of the seed continue from, see `GemRng::position`
+    pub gem_rng: u64,
+    pub combat_rng: u64,
+    pub towers: Vec<SavedTower>,
+    pub rocks: Vec<(i32, i32)>,
+    #[serde(default)]
+    pub gem_types: GemTypeStats,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct SavedTower {
+    pub tower: PlacedTower,
+    pub stats: TowerStats,
+    #[serde(default)]
+    pub targeting: TargetingMode,
+}
+
+impl SaveFile {
+    /// # Errors
+    ///
+    /// Will return `Err` if the file cannot be read or is not a save
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        read_ron(path.as_ref())
+    }
+
+    /// # Errors
+    ///
+    /// Will return `Err` if the file cannot be written
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        write_ron(self, path.as_ref())
+    }
+}
+
+/// Reads a RON file such as a save or a replay
+///
+/// # Errors
+///
+/// Will return `Err` if the file cannot be read or does not hold a `T`
+pub fn read_ron<T: DeserializeOwned>(path: &Path) -> anyhow::Result<T> {
+    let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    ron::de::from_bytes(&bytes).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Writes the value as pretty RON, creating the file's directory if needed
+///
+/// # Errors
+///
+/// Will return `Err` if the file cannot be written
+pub fn write_ron<T: Serialize>(value: &T, path: &Path) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let ron = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())?;
+    fs::write(path, ron).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Gems waiting to be chosen live outside of the saved state
+fn can_save(game_state: &State<GameState>, just_built: &Query<(), With<JustBuilt>>) -> bool {
+    if *game_state.current() != GameState::Play {
+        info!("Games can only be saved and loaded while playing");
+        return false;
+    }
+    if !just_built.is_empty() {
+        info!("Games can only be saved and loaded before building the next gems");
+        return false;
+    }
+    true
+}
+
+fn save(
+    mut er: EventReader<SaveGame>,
+    game_state: Res<State<GameState>>,
+    (args, seed, level): (Res<Args>, Res<Seed>, Res<Level>),
+    (health, gold, quality_level): (Res<PlayerHealth>, Res<Gold>, Res<QualityLevel>),
+    (gem_rng, combat_rng): (Res<GemRng>, Res<CombatRng>),
+    gem_types: Res<GemTypeStats>,
+    towers: Query<
+        (
+            &Transform,
+            Option<&Gem>,
+            Option<&Special>,
+            &TowerStats,
+            &TargetingMode,
+        ),
+        With<Tower>,
+    >,
+    rocks: Query<&Transform, With<Rock>>,
+    just_built: Query<(), With<JustBuilt>>,
+) {
+    if er.iter().next().is_none() || !can_save(&game_state, &just_built) {
+        return;
+    }
+
+    let towers = towers
+        .iter()
+        .filter_map(|(transform, gem, special, stats, targeting)| {
+            let tower = match (gem, special) {
+                (Some(Gem { quality, r#type }), _) => LayoutTower::Gem {
+                    r#type: *r#type,
+                    quality: *quality,
+                },
+                (None, Some(Special(r#type))) => LayoutTower::Special(*r#type),
+                (None, None) => return None,
+            };
+            Some(SavedTower {
+                tower: PlacedTower {
+                    pos: Grid::to_grid_pos(transform.translation),
+                    tower,
+                },
+                stats: *stats,
+                targeting: *targeting,
+            })
+        })
+        .collect();
+    let file = SaveFile {
+        map: args.map.clone(),
+        seed: seed.0,
+        level: level.0,
+        health: health.0,
+        gold: gold.0,
+        quality_level: quality_level.0,
+        gem_rng: gem_rng.position(),
+        combat_rng: combat_rng.position(),
+        towers,
+        rocks: rocks
+            .iter()
+            .map(|transform| Grid::to_grid_pos(transform.translation))
+            .collect(),
+        gem_types: gem_types.clone(),
+    };
+    match file.save(&args.save) {
+        Ok(()) => info!("Saved the game on level {}", level.0),
+        Err(err) => error!("Failed to save the game: {:?}", err),
+    }
+}
+
+fn load(
+    mut commands: Commands,
+    mut er: EventReader<LoadGame>,
+    mut loaded: EventWriter<GameLoaded>,
+    game_state: Res<State<GameState>>,
+    args: Res<Args>,
+    playback: Option<Res<Playback>>,
+    map: Option<Res<MapDefinition>>,
+    definitions: Option<Res<TowerDefinitions>>,
+    mut grid: ResMut<Grid>,
+    (mut seed, mut level, mut gem_types): (ResMut<Seed>, ResMut<Level>, ResMut<GemTypeStats>),
+    (mut health, mut gold, mut quality_level): (
+        ResMut<PlayerHealth>,
+        ResMut<Gold>,
+        ResMut<QualityLevel>,
+    ),
+    existing: Query<Entity, Or<(With<Tower>, With<Rock>)>>,
+    just_built: Query<(), With<JustBuilt>>,
+) {
+    if er.iter().next().is_none() || !can_save(&game_state, &just_built) {
+        return;
+    }
+    if playback.is_some() {
+        info!("Games cannot be loaded while a replay is playing");
+        return;
+    }
+    let (map, definitions) = match (map, definitions) {
+        (Some(map), Some(definitions)) => (map, definitions),
+        _ => return,
+    };
+
+    let file = match SaveFile::load(&args.save).and_then(|file| {
+        if file.map != map.name {
+            bail!("The save is for map {}, not {}", file.map, map.name);
+        }
+        Ok(file)
+    }) {
+        Ok(file) => file,
+        Err(err) => {
+            error!("Failed to load the game: {:?}", err);
+            return;
+        }
+    };
+
+    for entity in existing.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    fill_grid(&mut grid, &map);
+
+    for SavedTower {
+        tower: PlacedTower { pos, tower },
+        stats,
+        targeting,
+    } in file.towers
+    {
+        let mut entity = commands.spawn_bundle((
+            grid_transform(pos),
+            GlobalTransform::default(),
+            Tower,
+            stats,
+        ));
+        match tower {
+            LayoutTower::Gem { r#type, quality } => {
+                let gem = Gem { quality, r#type };
+                entity.insert_bundle(definitions.gem(gem)).insert(gem);
+            }
+            LayoutTower::Special(r#type) => {
+                entity
+                    .insert_bundle(definitions.special(r#type))
+                    .insert(Special(r#type));
+            }
+        }
+        // Replaces the default from the definitions
+        entity.insert(targeting);
+        let entity = entity.id();
+        grid.add_building(&footprint(pos), entity)
+            .map_err(|_| error!("Failed to add saved tower to {};{}", pos.0, pos.1))
+            .ok();
+    }
+    for pos in file.rocks {
+        let entity = commands
+            .spawn_bundle((grid_transform(pos), GlobalTransform::default(), Rock))
+            .id();
+        grid.add_building(&footprint(pos), entity)
+            .map_err(|_| error!("Failed to add saved rock to {};{}", pos.0, pos.1))
+            .ok();
+    }
+
+    *seed = Seed(file.seed);
+    commands.insert_resource(seed.gem_rng_at(file.gem_rng));
+    commands.insert_resource(seed.combat_rng_at(file.combat_rng));
+    *level = Level(file.level);
+    *health = PlayerHealth(file.health);
+    *gold = Gold(file.gold);
+    *quality_level = QualityLevel(file.quality_level);
+    *gem_types = file.gem_types;
+    loaded.send(GameLoaded);
+    info!("Loaded the game on level {}", file.level);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        simulation::Simulation,
+        towers::{GemQuality, GemType},
+    };
+
+    /// Everything a save has to bring back
+    #[derive(Debug, PartialEq)]
+    struct Snapshot {
+        towers: Vec<((i32, i32), Gem)>,
+        rocks: Vec<(i32, i32)>,
+        level: u32,
+        gold: u32,
+        gem_rng: u64,
+        combat_rng: u64,
+    }
+
+    fn snapshot(simulation: &mut Simulation) -> Snapshot {
+        let world = simulation.world_mut();
+        let mut towers = world
+            .query::<(&Transform, &Gem)>()
+            .iter(world)
+            .map(|(transform, gem)| (Grid::to_grid_pos(transform.translation), *gem))
+            .collect::<Vec<_>>();
+        towers.sort_by_key(|(pos, _)| *pos);
+        let mut rocks = world
+            .query_filtered::<&Transform, With<Rock>>()
+            .iter(world)
+            .map(|transform| Grid::to_grid_pos(transform.translation))
+            .collect::<Vec<_>>();
+        rocks.sort_unstable();
+        Snapshot {
+            towers,
+            rocks,
+            level: world.get_resource::<Level>().unwrap().0,
+            gold: world.get_resource::<Gold>().unwrap().0,
+            gem_rng: world.get_resource::<GemRng>().unwrap().position(),
+            combat_rng: world.get_resource::<CombatRng>().unwrap().position(),
+        }
+    }
+
+    #[test]
+    fn loads_what_was_saved() {
+        let mut simulation = Simulation::load(
+            "assets/maps/level_1.map",
+            "assets/gems.towers",
+            "assets/level_1.waves",
+        )
+        .unwrap();
+        let path = std::env::temp_dir().join("rusttd_loads_what_was_saved.save");
+        simulation
+            .world_mut()
+            .get_resource_mut::<Args>()
+            .unwrap()
+            .save = path.display().to_string();
+        let ruby = Gem {
+            quality: GemQuality::Chipped,
+            r#type: GemType::Ruby,
+        };
+        simulation.place_gem((-10, 4), ruby).unwrap();
+        simulation
+            .place_gem(
+                (-6, 4),
+                Gem {
+                    quality: GemQuality::Flawed,
+                    r#type: GemType::Opal,
+                },
+            )
+            .unwrap();
+        let world = simulation.world_mut();
+        let rock = world
+            .spawn()
+            .insert_bundle((grid_transform((-14, 4)), GlobalTransform::default(), Rock))
+            .id();
+        world
+            .get_resource_mut::<Grid>()
+            .unwrap()
+            .add_building(&footprint((-14, 4)), rock)
+            .unwrap();
+        let seed = *world.get_resource::<Seed>().unwrap();
+        world.insert_resource(Level(3));
+        world.insert_resource(Gold(42));
+        world.insert_resource(seed.gem_rng_at(100));
+        world.insert_resource(seed.combat_rng_at(7));
+        simulation.update();
+        let saved = snapshot(&mut simulation);
+        simulation.send(SaveGame);
+        simulation.update();
+
+        // Play on before loading, none of which should survive the load
+        simulation.place_gem((-18, 4), ruby).unwrap();
+        let world = simulation.world_mut();
+        world.insert_resource(Level(4));
+        world.insert_resource(Gold(0));
+        world.insert_resource(seed.gem_rng());
+        world.insert_resource(seed.combat_rng());
+        simulation.send(LoadGame);
+        simulation.update();
+        fs::remove_file(&path).ok();
+        assert_eq!(snapshot(&mut simulation), saved);
+    }
+}
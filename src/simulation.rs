@@ -0,0 +1,380 @@
+use crate::{
+    cli::Args,
+    grid::Grid,
+    level_1::{assets::GameState, LevelState},
+    maps::MapDefinition,
+    path,
+    rng::Seed,
+    towers::{
+        definitions::TowerDefinitions,
+        footprint, grid_transform,
+        special::{Special, SpecialType},
+        Gem, GemQuality, GemType, Tower, TowerBundle,
+    },
+    waves::WaveDefinitions,
+    GamePlugins,
+};
+use anyhow::{bail, Context};
+use bevy::{prelude::*, transform::TransformPlugin, utils::Instant};
+use serde::{Deserialize, Serialize};
+use std::{fs, time::Duration};
+
+/// Simulated time between two updates
+pub const STEP: Duration = Duration::from_micros(16_667);
+
+// Waves still running after this long are given up on, since creeps can get stuck on broken maps
+const MAX_WAVE_DURATION: Duration = Duration::from_secs(600);
+
+/// How a simulated wave ended
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Cleared,
+    GameOver,
+    TimedOut,
+}
+
+/// Towers to place before simulating, loaded from a RON file
+#[derive(Clone, Deserialize)]
+pub struct Layout {
+    pub towers: Vec<PlacedTower>,
+}
+
+#[derive(Clone, Copy, Deserialize, Serialize)]
+pub struct PlacedTower {
+    pub pos: (i32, i32),
+    pub tower: LayoutTower,
+}
+
+#[derive(Clone, Copy, Deserialize, Serialize)]
+pub enum LayoutTower {
+    Gem {
+        r#type: GemType,
+        quality: GemQuality,
+    },
+    Special(SpecialType),
+}
+
+impl Layout {
+    /// # Errors
+    ///
+    /// Will return `Err` if the RON is malformed
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        Ok(ron::de::from_bytes(bytes)?)
+    }
+}
+
+/// Runs the gameplay plugins without a window, rendering or input, advancing time by a fixed step per update
+pub struct Simulation {
+    app: App,
+    now: Instant,
+}
+
+impl Simulation {
+    /// # Panics
+    ///
+    /// Will panic if the map cannot be turned into a grid, see `level_1::map::build_grid`
+    pub fn new(map: MapDefinition, towers: TowerDefinitions, waves: WaveDefinitions) -> Self {
+        let mut app = App::new();
+        app.insert_resource(Args {
+            map: map.name.clone(),
+            ..Args::default()
+        })
+        .insert_resource(map)
+        .insert_resource(towers)
+        .insert_resource(waves)
+        .insert_resource(Time::default())
+        .add_plugin(TransformPlugin)
+        .add_state(GameState::Play)
+        .add_plugins(GamePlugins);
+
+        let mut simulation = Self {
+            app,
+            now: Instant::now(),
+        };
+        // Builds the grid
+        simulation.update();
+        simulation
+    }
+
+    /// Loads the definitions from the given files, paths are relative to the working directory
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if a file cannot be read or holds invalid definitions
+    pub fn load(map: &str, towers: &str, waves: &str) -> anyhow::Result<Self> {
+        let read = |path: &str| fs::read(path).with_context(|| format!("Failed to read {}", path));
+        Ok(Self::new(
+            MapDefinition::from_bytes(&read(map)?)?,
+            TowerDefinitions::from_bytes(&read(towers)?)?,
+            WaveDefinitions::from_bytes(&read(waves)?)?,
+        ))
+    }
+
+    /// Restarts every random number stream from the given seed
+    pub fn set_seed(&mut self, seed: u64) {
+        let seed = Seed(seed);
+        let world = &mut self.app.world;
+        world.insert_resource(seed.gem_rng());
+        world.insert_resource(seed.combat_rng());
+        world.insert_resource(seed);
+    }
+
+    pub fn world(&self) -> &World {
+        &self.app.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.app.world
+    }
+
+    /// Sends an event the way the player's input would, it is handled on the next update
+    ///
+    /// # Panics
+    ///
+    /// Will panic if no plugin added the event
+    pub fn send<E: Send + Sync + 'static>(&mut self, event: E) {
+        self.app
+            .world
+            .get_resource_mut::<Events<E>>()
+            .unwrap()
+            .send(event);
+    }
+
+    /// Advances the simulation by one `STEP`
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the `Time` resource was removed
+    pub fn update(&mut self) {
+        self.now += STEP;
+        let now = self.now;
+        self.app
+            .world
+            .get_resource_mut::<Time>()
+            .unwrap()
+            .update_with_instant(now);
+        self.app.update();
+    }
+
+    /// Places a finished gem tower, skipping the building and choosing phases
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the tower does not fit or would block the creeps' path
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the tower definitions resource was removed
+    pub fn place_gem(&mut self, pos: (i32, i32), gem: Gem) -> anyhow::Result<Entity> {
+        let bundle = self
+            .app
+            .world
+            .get_resource::<TowerDefinitions>()
+            .unwrap()
+            .gem(gem);
+        self.place(pos, bundle, gem)
+    }
+
+    /// Places a special tower, see `Simulation::place_gem`
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the tower does not fit or would block the creeps' path
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the tower definitions resource was removed
+    pub fn place_special(
+        &mut self,
+        pos: (i32, i32),
+        r#type: SpecialType,
+    ) -> anyhow::Result<Entity> {
+        let bundle = self
+            .app
+            .world
+            .get_resource::<TowerDefinitions>()
+            .unwrap()
+            .special(r#type);
+        self.place(pos, bundle, Special(r#type))
+    }
+
+    fn place(
+        &mut self,
+        pos: (i32, i32),
+        bundle: TowerBundle,
+        kind: impl Component,
+    ) -> anyhow::Result<Entity> {
+        let positions = footprint(pos);
+        {
+            let world = &self.app.world;
+            let grid = world.get_resource::<Grid>().unwrap();
+            let map = world.get_resource::<MapDefinition>().unwrap();
+            if !grid.buildable(&positions) {
+                bail!("Cannot build at {};{}", pos.0, pos.1);
+            }
+            if path::would_block(grid, &map.routes(), &positions) {
+                bail!(
+                    "Building at {};{} would block the creeps' path",
+                    pos.0,
+                    pos.1
+                );
+            }
+        }
+
+        let entity = self
+            .app
+            .world
+            .spawn()
+            .insert_bundle((grid_transform(pos), GlobalTransform::default(), Tower, kind))
+            .insert_bundle(bundle)
+            .id();
+        self.app
+            .world
+            .get_resource_mut::<Grid>()
+            .unwrap()
+            .add_building(&positions, entity)
+            .map_err(|_| anyhow::anyhow!("Failed to add building to {};{}", pos.0, pos.1))?;
+        Ok(entity)
+    }
+
+    /// Places every tower of the layout, returning the placed entities in the same order
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if any tower cannot be placed, see `Simulation::place_gem`
+    pub fn place_layout(&mut self, layout: &Layout) -> anyhow::Result<Vec<Entity>> {
+        layout
+            .towers
+            .iter()
+            .map(|PlacedTower { pos, tower }| match *tower {
+                LayoutTower::Gem { r#type, quality } => {
+                    self.place_gem(*pos, Gem { quality, r#type })
+                }
+                LayoutTower::Special(r#type) => self.place_special(*pos, r#type),
+            })
+            .collect()
+    }
+
+    /// Spawns the wave of the current level and simulates it until every creep is gone or the game is over
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if a wave is already running
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the state resources were removed
+    pub fn run_wave(&mut self) -> anyhow::Result<Outcome> {
+        self.app
+            .world
+            .get_resource_mut::<State<LevelState>>()
+            .unwrap()
+            .set(LevelState::Spawning)?;
+        // Enters the spawning state
+        self.update();
+        Ok(self.finish_wave())
+    }
+
+    /// Simulates until the level is back to building or the game is over, the wave starts by itself once a gem is
+    /// chosen
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the state resources were removed
+    pub fn finish_wave(&mut self) -> Outcome {
+        let steps = MAX_WAVE_DURATION.as_micros() / STEP.as_micros();
+        for _ in 0..steps {
+            self.update();
+            let world = &self.app.world;
+            if *world.get_resource::<State<GameState>>().unwrap().current() == GameState::GameOver {
+                return Outcome::GameOver;
+            }
+            if *world.get_resource::<State<LevelState>>().unwrap().current() == LevelState::Building
+            {
+                return Outcome::Cleared;
+            }
+        }
+        Outcome::TimedOut
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        player::{Gold, PlayerHealth, STARTING_HEALTH},
+        stats::WaveStats,
+    };
+
+    fn level_1() -> Simulation {
+        Simulation::load(
+            "assets/maps/level_1.map",
+            "assets/gems.towers",
+            "assets/level_1.waves",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn undefended_wave_leaks() {
+        let mut simulation = level_1();
+        assert_eq!(simulation.run_wave().unwrap(), Outcome::Cleared);
+        let health = simulation.world().get_resource::<PlayerHealth>().unwrap();
+        assert_eq!(health.0, STARTING_HEALTH - 10);
+    }
+
+    #[test]
+    fn towers_kill_creeps() {
+        let mut simulation = level_1();
+        let gem = Gem {
+            quality: GemQuality::Perfect,
+            r#type: GemType::Ruby,
+        };
+        simulation.place_gem((-10, 4), gem).unwrap();
+        simulation.place_gem((-6, 4), gem).unwrap();
+        assert_eq!(simulation.run_wave().unwrap(), Outcome::Cleared);
+        assert!(simulation.world().get_resource::<Gold>().unwrap().0 > 0);
+    }
+
+    #[test]
+    fn wave_ends_after_the_last_spawn() {
+        let mut simulation = level_1();
+        // Kills every creep before the next one spawns
+        let gem = Gem {
+            quality: GemQuality::Perfect,
+            r#type: GemType::Ruby,
+        };
+        simulation.place_gem((-18, 4), gem).unwrap();
+        assert_eq!(simulation.run_wave().unwrap(), Outcome::Cleared);
+        let wave = simulation.world().get_resource::<WaveStats>().unwrap();
+        assert_eq!(wave.kills, 10);
+    }
+
+    #[test]
+    fn cannot_block_the_path() {
+        let mut simulation = level_1();
+        let gem = Gem {
+            quality: GemQuality::Chipped,
+            r#type: GemType::Ruby,
+        };
+        // Wall the spawner into the corner of the map, leaving the top open until the last tower
+        for pos in [(-18, 0), (-18, 2), (-18, 4)] {
+            simulation.place_gem(pos, gem).unwrap();
+        }
+        let slots = |simulation: &Simulation| {
+            simulation
+                .world()
+                .get_resource::<Grid>()
+                .unwrap()
+                .keys()
+                .count()
+        };
+        let before = slots(&simulation);
+
+        let err = simulation.place_gem((-20, 4), gem).unwrap_err();
+        assert!(err.to_string().contains("block"), "{}", err);
+        assert_eq!(slots(&simulation), before);
+        let grid = simulation.world().get_resource::<Grid>().unwrap();
+        assert!(footprint((-20, 4)).iter().all(|pos| grid.walkable(*pos)));
+    }
+}
@@ -0,0 +1,100 @@
+use crate::{
+    creeps::DamageDealt,
+    level_1::{assets::GameState, LevelState},
+    towers::{Gem, GemType, Tower},
+};
+use bevy::prelude::{self, *};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, time::Duration};
+
+pub struct Plugin;
+
+impl prelude::Plugin for Plugin {
+    fn build(&self, app: &mut prelude::App) {
+        app.insert_resource(WaveStats::default())
+            .insert_resource(GemTypeStats::default())
+            .add_system(add_tower_stats)
+            // Runs in every state so the hits ending a wave are still counted
+            .add_system(record_damage)
+            .add_system_set(SystemSet::on_enter(LevelState::Spawning).with_system(reset_wave))
+            .add_system_set(SystemSet::on_update(LevelState::Spawning).with_system(time_wave))
+            .add_system_set(SystemSet::on_exit(GameState::GameOver).with_system(reset_gem_types));
+    }
+}
+
+/// Damage a tower has dealt since it was built
+#[derive(Clone, Component, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct TowerStats {
+    pub damage: u64,
+    pub kills: u32,
+    /// Damage wasted on creeps that had less life left than the hit dealt
+    pub overkill: u64,
+}
+
+/// Summary of the current or last wave
+#[derive(Clone, Debug, Default)]
+pub struct WaveStats {
+    pub kills: u32,
+    pub leaked: u32,
+    /// Time from the first spawn until the last creep was killed or leaked
+    pub duration: Duration,
+    /// What each tower dealt during the wave
+    pub towers: HashMap<Entity, TowerStats>,
+}
+
+/// Damage dealt by every gem of each type since the game started, kept when gems are combined or turned into rocks
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct GemTypeStats(pub HashMap<GemType, TowerStats>);
+
+impl TowerStats {
+    fn add(&mut self, hit: &DamageDealt) {
+        self.damage += hit.amount;
+        self.overkill += hit.overkill;
+        if hit.killed {
+            self.kills += 1;
+        }
+    }
+}
+
+/// Loaded towers keep their saved stats
+fn add_tower_stats(
+    mut commands: Commands,
+    towers: Query<Entity, (Added<Tower>, Without<TowerStats>)>,
+) {
+    for entity in towers.iter() {
+        commands.entity(entity).insert(TowerStats::default());
+    }
+}
+
+/// Every damage path sends `DamageDealt` with the tower it came from, so this is the only place stats are counted
+fn record_damage(
+    mut er: EventReader<DamageDealt>,
+    mut wave: ResMut<WaveStats>,
+    mut gem_types: ResMut<GemTypeStats>,
+    mut towers: Query<(&mut TowerStats, Option<&Gem>)>,
+) {
+    for hit in er.iter() {
+        if hit.killed {
+            wave.kills += 1;
+        }
+        if let Ok((mut stats, gem)) = towers.get_mut(hit.source) {
+            stats.add(hit);
+            wave.towers.entry(hit.source).or_default().add(hit);
+            if let Some(gem) = gem {
+                gem_types.0.entry(gem.r#type).or_default().add(hit);
+            }
+        }
+    }
+}
+
+fn reset_wave(mut wave: ResMut<WaveStats>) {
+    *wave = WaveStats::default();
+}
+
+fn time_wave(time: Res<Time>, mut wave: ResMut<WaveStats>) {
+    wave.duration += time.delta();
+}
+
+fn reset_gem_types(mut gem_types: ResMut<GemTypeStats>) {
+    *gem_types = GemTypeStats::default();
+}
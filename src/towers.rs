@@ -1,59 +1,82 @@
+use self::{attack::AttackStyle, definitions::TowerDefinitions, targeting::TargetingMode};
 use crate::{
     abilities::{aura::Auras, OnHitAbilities},
-    buffs, creeps,
+    creeps,
+    damage::DamageType,
     grid::Grid,
-    level_1::LevelState,
+    level_1::{assets::GameState, LevelState},
+    maps::MapDefinition,
+    path,
+    player::QualityLevel,
+    rng::GemRng,
+};
+use bevy::{
+    math::Vec3Swizzles,
+    prelude::{self, *},
 };
-use bevy::prelude::{self, *};
 use rand::{
-    distributions::Standard,
+    distributions::{Standard, WeightedIndex},
     prelude::{Distribution, IteratorRandom},
+    Rng,
 };
-use std::{ops::RangeInclusive, time::Duration};
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
-// Most towers attack at a base rate of 1 attack per second
-pub const BASE_TOWER_SPEED: f32 = 1.0;
-
-mod amethyst;
-mod aquamarine;
-mod diamond;
-pub mod emerald;
-mod opal;
-mod ruby;
-pub mod sapphire;
-mod topaz;
+pub mod attack;
+pub mod definitions;
+pub mod recipes;
+pub mod special;
+pub mod targeting;
 
 pub struct Plugin;
 
 impl prelude::Plugin for Plugin {
     fn build(&self, app: &mut prelude::App) {
-        app.add_plugin(diamond::Plugin)
-            .add_plugin(aquamarine::Plugin)
-            .add_plugin(amethyst::Plugin)
-            .add_plugin(emerald::Plugin)
-            .add_plugin(opal::Plugin)
-            .add_plugin(ruby::Plugin)
-            .add_plugin(sapphire::Plugin)
-            .add_plugin(topaz::Plugin)
+        app.add_plugin(attack::Plugin)
+            .add_plugin(recipes::Plugin)
+            .add_plugin(targeting::Plugin)
             .add_event::<BuildGem>()
             .add_event::<ChooseGem>()
+            .add_event::<CombineGem>()
             .add_event::<ProjectileHit>()
-            .insert_resource(RangeDisplay::Off)
-            .add_system(render_range)
             .add_system_set(SystemSet::on_update(LevelState::Building).with_system(build_gem))
             .add_system_set(SystemSet::on_enter(LevelState::Choosing).with_system(reveal_gems))
-            .add_system_set(SystemSet::on_update(LevelState::Choosing).with_system(choose_gem))
+            .add_system_set(
+                SystemSet::on_update(LevelState::Choosing)
+                    .with_system(choose_gem)
+                    .with_system(combine_gem),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::GameOver).with_system(despawn_projectiles),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::GameOver).with_system(despawn_towers))
+            .add_system(move_projectile);
+    }
+}
+
+/// Meshes for towers, rocks and projectiles spawned by the gameplay systems
+pub struct RenderPlugin;
+
+impl prelude::Plugin for RenderPlugin {
+    fn build(&self, app: &mut prelude::App) {
+        app.insert_resource(RangeDisplay::Off)
+            .add_system(render_range)
+            .add_system(render_holes)
+            .add_system(render_gems)
+            .add_system(render_rocks)
+            .add_system(render_projectiles)
             .add_system_set(
                 SystemSet::on_exit(LevelState::Choosing).with_system(despawn_range_render),
             )
-            .add_system(move_projectile)
-            .add_system(pick_target);
+            .add_system_set(SystemSet::on_exit(GameState::GameOver).with_system(despawn_ranges));
     }
 }
 
-#[derive(Clone, Copy, EnumIter)]
+#[derive(
+    Clone, Copy, Debug, Deserialize, EnumIter, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize,
+)]
 pub enum GemQuality {
     Chipped,
     Flawed,
@@ -62,13 +85,53 @@ pub enum GemQuality {
     Perfect,
 }
 
-impl Distribution<GemQuality> for Standard {
+impl GemQuality {
+    /// Returns the quality the given number of tiers above this one, or `None` if that would exceed `GemQuality::Perfect`
+    pub fn upgraded(self, tiers: usize) -> Option<GemQuality> {
+        GemQuality::iter()
+            .skip_while(|quality| *quality != self)
+            .nth(tiers)
+    }
+}
+
+// Percent chance of rolling each quality from chipped to perfect, indexed by the player's quality level
+const QUALITY_CHANCES: [[u32; 5]; 9] = [
+    [100, 0, 0, 0, 0],
+    [70, 30, 0, 0, 0],
+    [60, 30, 10, 0, 0],
+    [50, 30, 20, 0, 0],
+    [40, 30, 20, 10, 0],
+    [30, 30, 30, 10, 0],
+    [20, 30, 30, 20, 0],
+    [10, 30, 30, 30, 0],
+    [0, 30, 30, 30, 10],
+];
+
+#[allow(clippy::cast_possible_truncation)]
+pub const MAX_QUALITY_LEVEL: u32 = QUALITY_CHANCES.len() as u32 - 1;
+
+/// Distribution of gem qualities weighted by the player's quality level
+pub struct QualityDistribution(WeightedIndex<u32>);
+
+impl QualityDistribution {
+    /// # Panics
+    ///
+    /// Will panic if a row of `QUALITY_CHANCES` has no positive weight
+    pub fn new(level: u32) -> Self {
+        let level = level.min(MAX_QUALITY_LEVEL) as usize;
+        Self(WeightedIndex::new(&QUALITY_CHANCES[level]).unwrap())
+    }
+}
+
+impl Distribution<GemQuality> for QualityDistribution {
     fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> GemQuality {
-        GemQuality::iter().choose(rng).unwrap()
+        GemQuality::iter().nth(self.0.sample(rng)).unwrap()
     }
 }
 
-#[derive(Clone, Copy, EnumIter)]
+#[derive(
+    Clone, Copy, Debug, Deserialize, EnumIter, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize,
+)]
 pub enum GemType {
     Diamond,
     Aquamarine,
@@ -101,7 +164,7 @@ impl Distribution<GemType> for Standard {
     }
 }
 
-#[derive(Component)]
+#[derive(Clone, Component, Copy, Debug, PartialEq, Eq)]
 pub struct Gem {
     pub quality: GemQuality,
     pub r#type: GemType,
@@ -120,17 +183,8 @@ impl Gem {
         }
     }
 
-    pub fn tower(&self) -> TowerBundle {
-        match self.r#type {
-            GemType::Diamond => diamond::tower(self.quality),
-            GemType::Aquamarine => aquamarine::tower(self.quality),
-            GemType::Ruby => ruby::tower(self.quality),
-            GemType::Emerald => emerald::tower(self.quality),
-            GemType::Sapphire => sapphire::tower(self.quality),
-            GemType::Topaz => topaz::tower(self.quality),
-            GemType::Opal => opal::tower(self.quality),
-            GemType::Amethyst => amethyst::tower(self.quality),
-        }
+    pub fn tower(&self, definitions: &TowerDefinitions) -> TowerBundle {
+        definitions.gem(*self)
     }
 }
 
@@ -145,31 +199,27 @@ pub struct BuildGem {
 fn build_gem(
     mut commands: Commands,
     mut er: EventReader<BuildGem>,
-    ass: ResMut<AssetServer>,
+    map: Res<MapDefinition>,
     mut grid: ResMut<Grid>,
 ) {
+    let routes = map.routes();
     for BuildGem { pos } in er.iter() {
-        let positions = [
-            *pos,
-            (pos.0 + 1, pos.1),
-            (pos.0, pos.1 + 1),
-            (pos.0 + 1, pos.1 + 1),
-        ];
+        let positions = footprint(*pos);
 
         if !grid.buildable(&positions) {
             continue;
         }
 
-        let mesh: Handle<Mesh> = ass.load("hole.gltf#Mesh0/Primitive0");
-        let material: Handle<StandardMaterial> = ass.load("hole.gltf#Material0");
+        if path::would_block(&grid, &routes, &positions) {
+            info!(
+                "Building at {};{} would block the creeps' path",
+                pos.0, pos.1
+            );
+            continue;
+        }
+
         let entity = commands
-            .spawn_bundle(PbrBundle {
-                mesh,
-                material,
-                transform: Transform::from_translation(Vec3::new(pos.0 as f32, 0.5, pos.1 as f32)),
-                ..PbrBundle::default()
-            })
-            .insert(JustBuilt)
+            .spawn_bundle((grid_transform(*pos), GlobalTransform::default(), JustBuilt))
             .id();
         grid.add_building(&positions, entity)
             .map_err(|_| info!("Failed to add building to {};{}", pos.0, pos.1))
@@ -183,19 +233,20 @@ pub struct ChooseGem {
 
 fn reveal_gems(
     mut commands: Commands,
-    ass: ResMut<AssetServer>,
+    definitions: Res<TowerDefinitions>,
+    quality_level: Res<QualityLevel>,
+    mut rng: ResMut<GemRng>,
     mut gems: Query<Entity, With<JustBuilt>>,
 ) {
+    let qualities = QualityDistribution::new(quality_level.0);
     for entity in gems.iter_mut() {
-        let r#type: GemType = rand::random();
-        let quality: GemQuality = rand::random();
+        let r#type: GemType = rng.0.gen();
+        let quality = qualities.sample(&mut rng.0);
         let gem = Gem { quality, r#type };
-        let mesh: Handle<Mesh> = ass.load("clearcube.gltf#Mesh0/Primitive0");
-        let material: Handle<StandardMaterial> = ass.load("clearcube.gltf#Material0");
         commands
             .entity(entity)
-            .insert_bundle(gem.tower())
-            .insert_bundle((gem, Tower, mesh, material));
+            .insert_bundle(gem.tower(&definitions))
+            .insert_bundle((gem, Tower));
     }
 }
 
@@ -206,7 +257,6 @@ fn choose_gem(
     mut commands: Commands,
     mut er: EventReader<ChooseGem>,
     grid: ResMut<Grid>,
-    ass: ResMut<AssetServer>,
     mut gems: Query<Entity, With<JustBuilt>>,
 ) {
     for ChooseGem { pos } in er.iter() {
@@ -217,14 +267,7 @@ fn choose_gem(
 
             for entity in gems.iter_mut() {
                 if entity != chosen_entity {
-                    let mesh: Handle<Mesh> = ass.load("ps1wall.gltf#Mesh0/Primitive0");
-                    let mat: Handle<StandardMaterial> = ass.load("ps1wall.gltf#Material0");
-                    commands
-                        .entity(entity)
-                        .remove::<Gem>()
-                        .remove_bundle::<TowerBundle>()
-                        .remove::<Tower>()
-                        .insert_bundle((Rock, mesh, mat));
+                    turn_into_rock(&mut commands, entity);
                 }
                 commands.entity(entity).remove::<JustBuilt>();
             }
@@ -232,12 +275,147 @@ fn choose_gem(
     }
 }
 
+pub struct CombineGem {
+    pub pos: (i32, i32),
+}
+
+/// Combines the chosen gem with identical gems built this round, two identical gems upgrade the chosen gem by one
+/// quality tier and four upgrade it by two. All other gems built this round turn into rocks.
+fn combine_gem(
+    mut commands: Commands,
+    mut er: EventReader<CombineGem>,
+    grid: Res<Grid>,
+    definitions: Res<TowerDefinitions>,
+    gems: Query<(Entity, &Gem), With<JustBuilt>>,
+) {
+    for CombineGem { pos } in er.iter() {
+        let chosen_entity = if let Some(entity) = grid.get(*pos) {
+            entity
+        } else {
+            continue;
+        };
+
+        let chosen = if let Ok((_, gem)) = gems.get(chosen_entity) {
+            *gem
+        } else {
+            continue;
+        };
+
+        let matching = gems.iter().filter(|(_, gem)| **gem == chosen).count();
+        let quality = match matching {
+            0 | 1 => None,
+            2 | 3 => chosen.quality.upgraded(1),
+            _ => chosen
+                .quality
+                .upgraded(2)
+                .or_else(|| chosen.quality.upgraded(1)),
+        };
+
+        let quality = if let Some(quality) = quality {
+            quality
+        } else {
+            info!(
+                "Cannot combine {:?} {:?} with {} identical gems",
+                chosen.quality, chosen.r#type, matching
+            );
+            continue;
+        };
+
+        let upgraded = Gem {
+            quality,
+            r#type: chosen.r#type,
+        };
+        commands
+            .entity(chosen_entity)
+            .insert_bundle(upgraded.tower(&definitions))
+            .insert(upgraded);
+
+        for (entity, _) in gems.iter() {
+            if entity != chosen_entity {
+                turn_into_rock(&mut commands, entity);
+            }
+            commands.entity(entity).remove::<JustBuilt>();
+        }
+    }
+}
+
+fn turn_into_rock(commands: &mut Commands, entity: Entity) {
+    commands
+        .entity(entity)
+        .remove::<Gem>()
+        .remove_bundle::<TowerBundle>()
+        .remove::<Tower>()
+        .insert(Rock);
+}
+
+/// Where a tower built at the given grid position stands
+#[allow(clippy::cast_precision_loss)]
+pub fn grid_transform(pos: (i32, i32)) -> Transform {
+    Transform::from_translation(Vec3::new(pos.0 as f32, 0.5, pos.1 as f32))
+}
+
+/// The four grid positions a tower built at the given position occupies
+pub fn footprint(pos: (i32, i32)) -> [(i32, i32); 4] {
+    [
+        pos,
+        (pos.0 + 1, pos.1),
+        (pos.0, pos.1 + 1),
+        (pos.0 + 1, pos.1 + 1),
+    ]
+}
+
+fn render_holes(
+    mut commands: Commands,
+    ass: Res<AssetServer>,
+    holes: Query<(Entity, &Transform), Added<JustBuilt>>,
+) {
+    for (entity, transform) in holes.iter() {
+        commands.entity(entity).insert_bundle(PbrBundle {
+            mesh: ass.load("hole.gltf#Mesh0/Primitive0"),
+            material: ass.load("hole.gltf#Material0"),
+            transform: *transform,
+            ..PbrBundle::default()
+        });
+    }
+}
+
+fn render_gems(
+    mut commands: Commands,
+    ass: Res<AssetServer>,
+    towers: Query<(Entity, &Transform), Added<Tower>>,
+) {
+    for (entity, transform) in towers.iter() {
+        commands.entity(entity).insert_bundle(PbrBundle {
+            mesh: ass.load("clearcube.gltf#Mesh0/Primitive0"),
+            material: ass.load("clearcube.gltf#Material0"),
+            transform: *transform,
+            ..PbrBundle::default()
+        });
+    }
+}
+
+fn render_rocks(
+    mut commands: Commands,
+    ass: Res<AssetServer>,
+    rocks: Query<(Entity, &Transform), Added<Rock>>,
+) {
+    for (entity, transform) in rocks.iter() {
+        commands.entity(entity).insert_bundle(PbrBundle {
+            mesh: ass.load("ps1wall.gltf#Mesh0/Primitive0"),
+            material: ass.load("ps1wall.gltf#Material0"),
+            transform: *transform,
+            ..PbrBundle::default()
+        });
+    }
+}
+
 #[derive(Clone, Component, Copy)]
 pub struct Projectile {
     pub origin: Entity,
     pub target: Entity,
 }
 
+/// Sent when a projectile reaches its target, or straight away for attacks without projectiles
 pub struct ProjectileHit(pub Projectile);
 
 fn move_projectile(
@@ -267,24 +445,62 @@ fn move_projectile(
     }
 }
 
-#[derive(Component)]
+fn despawn_projectiles(mut commands: Commands, projectiles: Query<Entity, With<Projectile>>) {
+    for entity in projectiles.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Removes everything the player has built, the grid itself is rebuilt when the game starts again
+fn despawn_towers(
+    mut commands: Commands,
+    towers: Query<Entity, Or<(With<Tower>, With<Rock>, With<JustBuilt>)>>,
+) {
+    for entity in towers.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[derive(Clone, Component, Deserialize)]
 pub enum Damage {
     Range(RangeInclusive<u64>),
     Fixed(u64),
 }
 
+/// Attack speed including buffs, derived from `BaseAttackSpeed` by `buffs::Buffs`
 #[derive(Component)]
 pub struct AttackSpeed(pub f32);
 
-#[derive(Clone, Component, Copy)]
+/// Attack speed from the definitions
+#[derive(Component)]
+pub struct BaseAttackSpeed(pub f32);
+
+/// Range including buffs for towers, derived from `BaseRange` by `buffs::Buffs`
+#[derive(Clone, Component, Copy, Debug, Deserialize, PartialEq)]
 pub struct Range(pub f32);
 
+/// Range from the definitions
+#[derive(Component)]
+pub struct BaseRange(pub f32);
+
+/// Time until the tower can attack again. Does not repeat, so it stays finished until the tower attacks
 #[derive(Component)]
 pub struct Cooldown(Timer);
 
+impl Default for Cooldown {
+    fn default() -> Self {
+        // The duration follows the attack speed every frame
+        Self(Timer::from_seconds(1.0, false))
+    }
+}
+
 #[derive(Component, Default)]
 pub struct Target(Option<Entity>);
 
+/// Restricts a tower to only target creeps of the given type
+#[derive(Component, Default)]
+pub struct TargetFilter(pub Option<creeps::Type>);
+
 #[derive(Component)]
 pub struct Tower;
 
@@ -292,23 +508,27 @@ pub struct Tower;
 pub struct TowerBundle {
     name: Name,
     damage: Damage,
+    damage_type: DamageType,
+    base_speed: BaseAttackSpeed,
     speed: AttackSpeed,
+    base_range: BaseRange,
     range: Range,
     cooldown: Cooldown,
     abilities: OnHitAbilities,
     auras: Auras,
     target: Target,
+    filter: TargetFilter,
+    targeting: TargetingMode,
+    attack: AttackStyle,
 }
 
-fn launch_projectile(
-    commands: &mut Commands,
-    meshes: &mut ResMut<Assets<Mesh>>,
-    gem_position: &GlobalTransform,
-    gem_entity: Entity,
-    closest_creep: Entity,
+fn render_projectiles(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    projectiles: Query<(Entity, &Transform), Added<Projectile>>,
 ) {
-    commands
-        .spawn_bundle(PbrBundle {
+    for (entity, transform) in projectiles.iter() {
+        commands.entity(entity).insert_bundle(PbrBundle {
             mesh: meshes.add(
                 shape::Icosphere {
                     radius: 0.1,
@@ -316,93 +536,10 @@ fn launch_projectile(
                 }
                 .into(),
             ),
-            transform: Transform::from_translation(gem_position.translation),
+            transform: *transform,
             ..PbrBundle::default()
-        })
-        .insert(Projectile {
-            origin: gem_entity,
-            target: closest_creep,
         });
-}
-
-fn cooldown_is_done(cooldown: &mut Cooldown, speed: f32, time: &Time) -> bool {
-    cooldown
-        .0
-        .set_duration(Duration::from_secs_f32(1.0 * speed));
-    cooldown.0.tick(time.delta());
-    cooldown.0.finished()
-}
-
-fn get_closest_creep_within_range(
-    creeps: &Query<(Entity, &GlobalTransform, &creeps::Type)>,
-    tower_position: &GlobalTransform,
-    range: f32,
-    filter: Option<creeps::Type>,
-) -> Option<Entity> {
-    let mut closest = None;
-    let mut closest_distance = f32::INFINITY;
-    for (creep, position, r#type) in creeps.iter() {
-        if let Some(filter) = filter {
-            match (filter, *r#type) {
-                (creeps::Type::Ground, creeps::Type::Ground)
-                | (creeps::Type::Flying, creeps::Type::Flying) => {}
-                _ => continue,
-            }
-        }
-        let distance = tower_position
-            .translation
-            .distance_squared(position.translation);
-
-        if distance < closest_distance {
-            closest = Some(creep);
-            closest_distance = distance;
-        }
     }
-
-    if closest_distance >= range.powf(2.0) {
-        return None;
-    }
-
-    closest
-}
-
-fn get_all_creeps_within_range(
-    creeps: &Query<(Entity, &GlobalTransform, &creeps::Type)>,
-    tower_position: &GlobalTransform,
-    range: Range,
-    filter: Option<creeps::Type>,
-) -> Vec<Entity> {
-    creeps
-        .iter()
-        .filter_map(|(entity, transform, r#type)| {
-            if range.within(transform.translation, tower_position.translation) {
-                filter.map_or(Some(entity), |filter| match (filter, *r#type) {
-                    (creeps::Type::Ground, creeps::Type::Ground)
-                    | (creeps::Type::Flying, creeps::Type::Flying) => Some(entity),
-                    _ => None,
-                })
-            } else {
-                None
-            }
-        })
-        .collect()
-}
-
-pub fn get_all_towers_within_range(
-    towers: &Query<(Entity, &GlobalTransform, Option<&buffs::AttackSpeed>), With<Tower>>,
-    tower_position: &Vec3,
-    range: Range,
-) -> Vec<Entity> {
-    towers
-        .iter()
-        .filter_map(|(entity, transform, _)| {
-            if range.within(transform.translation, *tower_position) {
-                Some(entity)
-            } else {
-                None
-            }
-        })
-        .collect()
 }
 
 #[allow(dead_code)]
@@ -446,6 +583,12 @@ fn render_range(
     }
 }
 
+fn despawn_ranges(mut commands: Commands, ranges: Query<Entity, With<RangeVisualization>>) {
+    for entity in ranges.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
 fn despawn_range_render(
     mut commands: Commands,
     removed: Query<Entity, With<Rock>>,
@@ -463,32 +606,14 @@ fn despawn_range_render(
 impl Range {
     pub fn within(&self, target: Vec3, origin: Vec3) -> bool {
         // Check if within range's bounding box just to avoid the expensive sqrt in distance calc
-        (target.x >= origin.x - (self.0 / 2.0)
-            && target.x <= origin.x + (self.0 / 2.0)
-            && target.z >= origin.z - (self.0 / 2.0)
-            && target.z <= origin.z + (self.0 / 2.0))
+        (target.x >= origin.x - self.0
+            && target.x <= origin.x + self.0
+            && target.z >= origin.z - self.0
+            && target.z <= origin.z + self.0)
             && (
-                // Now check all within bounding box to make splash circular rather than square
-                target.distance(origin) <= self.0
+                // Now check all within bounding box to make splash circular rather than square, ignoring
+                // height so flying creeps are as easy to reach as ground creeps
+                target.xz().distance(origin.xz()) <= self.0
             )
     }
 }
-
-fn pick_target(
-    mut towers: Query<(&GlobalTransform, &Range, &mut Target), Without<creeps::Type>>,
-    creeps: Query<(Entity, &GlobalTransform, &creeps::Type)>,
-) {
-    for (tower_pos, range, mut target) in towers.iter_mut() {
-        if let Some(target_entity) = target.0 {
-            if let Ok(creep_pos) = creeps.get_component::<GlobalTransform>(target_entity) {
-                if !range.within(creep_pos.translation, tower_pos.translation) {
-                    target.0 = None;
-                }
-            } else {
-                target.0 = None;
-            }
-        } else {
-            target.0 = get_closest_creep_within_range(&creeps, tower_pos, range.0, None);
-        }
-    }
-}
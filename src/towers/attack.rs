@@ -0,0 +1,351 @@
+use super::{
+    targeting::{self, Candidate, TargetingMode},
+    AttackSpeed, Cooldown, Projectile, ProjectileHit, Range, Target, TargetFilter, Tower,
+};
+use crate::{
+    creeps::{self, Life, Movement},
+    level_1::LevelState,
+};
+use bevy::{
+    math::Vec3Swizzles,
+    prelude::{self, *},
+};
+use serde::Deserialize;
+use std::time::Duration;
+
+/// A single system attacks with every tower, what an attack hits depends on the tower's `AttackStyle`
+pub struct Plugin;
+
+impl prelude::Plugin for Plugin {
+    fn build(&self, app: &mut prelude::App) {
+        app.add_system_set(SystemSet::on_update(LevelState::Spawning).with_system(attack));
+    }
+}
+
+// Half the width of a beam, creeps this close to its line are hit
+const BEAM_WIDTH: f32 = 0.5;
+
+/// How a tower attacks once its cooldown is done, defaults to a projectile at its target
+#[derive(Clone, Component, Copy, Debug, Deserialize, PartialEq)]
+pub enum AttackStyle {
+    /// One projectile at the target
+    Projectile,
+    /// One projectile at each of up to `targets` creeps within range in targeting order, every creep within range if
+    /// there is no limit
+    Multi { targets: Option<usize> },
+    /// Hits the target straight away
+    Instant,
+    /// Hits the target and every creep on the line from the tower through it, up to the tower's range
+    Beam,
+    /// Hits the target, then jumps up to `jumps` times to the closest creep within `range` not hit yet
+    Chain { jumps: usize, range: Range },
+}
+
+impl Default for AttackStyle {
+    fn default() -> Self {
+        AttackStyle::Projectile
+    }
+}
+
+/// What an attack hits, projectiles hit when they arrive
+#[derive(Debug, PartialEq)]
+enum Hits {
+    Projectiles(Vec<Entity>),
+    Instant(Vec<Entity>),
+}
+
+impl Hits {
+    fn is_empty(&self) -> bool {
+        match self {
+            Hits::Projectiles(targets) | Hits::Instant(targets) => targets.is_empty(),
+        }
+    }
+}
+
+impl AttackStyle {
+    /// Picks what an attack hits, the tower's target is already picked by its targeting mode
+    fn hits(
+        self,
+        tower: Vec3,
+        range: Range,
+        filter: Option<creeps::Type>,
+        mode: TargetingMode,
+        target: Option<Entity>,
+        candidates: &[Candidate],
+    ) -> Hits {
+        let target = target.and_then(|target| {
+            candidates
+                .iter()
+                .find(|candidate| candidate.entity == target)
+        });
+        match self {
+            AttackStyle::Projectile => {
+                Hits::Projectiles(target.map(|target| target.entity).into_iter().collect())
+            }
+            AttackStyle::Multi { targets } => Hits::Projectiles(
+                mode.sort(tower, range, filter, candidates)
+                    .into_iter()
+                    .take(targets.unwrap_or(usize::MAX))
+                    .map(|candidate| candidate.entity)
+                    .collect(),
+            ),
+            AttackStyle::Instant => {
+                Hits::Instant(target.map(|target| target.entity).into_iter().collect())
+            }
+            AttackStyle::Beam => Hits::Instant(target.map_or_else(Vec::new, |target| {
+                beam(tower, target, range, filter, candidates)
+            })),
+            AttackStyle::Chain { jumps, range } => {
+                Hits::Instant(target.map_or_else(Vec::new, |target| {
+                    chain(target, jumps, range, filter, candidates)
+                }))
+            }
+        }
+    }
+}
+
+/// Creeps within range of the tower and within `BEAM_WIDTH` of the line from the tower through the target, only the
+/// target if it is right above or below the tower and there is no line
+fn beam(
+    tower: Vec3,
+    target: &Candidate,
+    range: Range,
+    filter: Option<creeps::Type>,
+    candidates: &[Candidate],
+) -> Vec<Entity> {
+    let direction = (target.position.xz() - tower.xz()).normalize_or_zero();
+    if direction == Vec2::ZERO {
+        return vec![target.entity];
+    }
+    targeting::reachable(tower, range, filter, candidates)
+        .filter(|candidate| {
+            let offset = candidate.position.xz() - tower.xz();
+            let along = offset.dot(direction);
+            along >= 0.0 && (offset - direction * along).length() <= BEAM_WIDTH
+        })
+        .map(|candidate| candidate.entity)
+        .collect()
+}
+
+/// The target followed by the creeps the chain jumps to, each the closest to the last one hit
+fn chain(
+    target: &Candidate,
+    jumps: usize,
+    range: Range,
+    filter: Option<creeps::Type>,
+    candidates: &[Candidate],
+) -> Vec<Entity> {
+    let mut hits = vec![target.entity];
+    let mut last = target;
+    for _ in 0..jumps {
+        let next = TargetingMode::Closest
+            .sort(last.position, range, filter, candidates)
+            .into_iter()
+            .find(|candidate| !hits.contains(&candidate.entity));
+        match next {
+            Some(next) => {
+                hits.push(next.entity);
+                last = next;
+            }
+            None => break,
+        }
+    }
+    hits
+}
+
+fn cooldown_is_done(cooldown: &mut Cooldown, speed: f32, time: &Time) -> bool {
+    cooldown
+        .0
+        .set_duration(Duration::from_secs_f32(1.0 * speed));
+    cooldown.0.tick(time.delta());
+    cooldown.0.finished()
+}
+
+fn attack(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut ew: EventWriter<ProjectileHit>,
+    mut towers: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            &AttackStyle,
+            &AttackSpeed,
+            &Range,
+            &TargetFilter,
+            &TargetingMode,
+            &Target,
+            &mut Cooldown,
+        ),
+        With<Tower>,
+    >,
+    creeps: Query<(Entity, &GlobalTransform, &creeps::Type, &Life, &Movement)>,
+) {
+    let candidates = Candidate::all(&creeps);
+
+    for (
+        tower,
+        tower_position,
+        style,
+        AttackSpeed(speed),
+        range,
+        TargetFilter(filter),
+        mode,
+        Target(target),
+        mut cooldown,
+    ) in towers.iter_mut()
+    {
+        if !cooldown_is_done(&mut *cooldown, *speed, &time) {
+            continue;
+        }
+
+        let hits = style.hits(
+            tower_position.translation,
+            *range,
+            *filter,
+            *mode,
+            *target,
+            &candidates,
+        );
+        // Towers with nothing to hit attack as soon as a creep comes into range
+        if hits.is_empty() {
+            continue;
+        }
+        cooldown.0.reset();
+
+        match hits {
+            Hits::Projectiles(targets) => {
+                for target in targets {
+                    commands.spawn_bundle((
+                        Transform::from_translation(tower_position.translation),
+                        GlobalTransform::default(),
+                        Projectile {
+                            origin: tower,
+                            target,
+                        },
+                    ));
+                }
+            }
+            Hits::Instant(targets) => {
+                for target in targets {
+                    ew.send(ProjectileHit(Projectile {
+                        origin: tower,
+                        target,
+                    }));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::towers::targeting::candidate;
+
+    /// Ground creep at `x`, as far along its route as it is from the tower
+    fn ground(id: u32, x: f32) -> Candidate {
+        candidate(id, x, creeps::Type::Ground, 10, x)
+    }
+
+    fn off_axis(id: u32, x: f32, z: f32) -> Candidate {
+        Candidate {
+            position: Vec3::new(x, 0.0, z),
+            ..ground(id, x)
+        }
+    }
+
+    fn hits(style: AttackStyle, target: u32, candidates: &[Candidate]) -> Hits {
+        style.hits(
+            Vec3::ZERO,
+            Range(5.0),
+            None,
+            TargetingMode::First,
+            Some(Entity::new(target)),
+            candidates,
+        )
+    }
+
+    #[test]
+    fn cooldown_waits_for_a_target() {
+        let mut cooldown = Cooldown::default();
+        let mut time = Time::default();
+        let start = time.startup();
+        time.update_with_instant(start);
+        time.update_with_instant(start + Duration::from_secs(2));
+        assert!(cooldown_is_done(&mut cooldown, 1.0, &time));
+        // Still ready on the next frame when nothing was attacked
+        time.update_with_instant(start + Duration::from_millis(2100));
+        assert!(cooldown_is_done(&mut cooldown, 1.0, &time));
+        cooldown.0.reset();
+        assert!(!cooldown_is_done(&mut cooldown, 1.0, &time));
+    }
+
+    #[test]
+    fn multi_follows_targeting() {
+        let candidates = vec![
+            ground(0, 3.0),
+            ground(1, 1.0),
+            ground(2, 2.0),
+            // Out of range
+            off_axis(3, 0.5, 9.0),
+        ];
+        assert_eq!(
+            hits(AttackStyle::Multi { targets: Some(2) }, 1, &candidates),
+            Hits::Projectiles(vec![Entity::new(1), Entity::new(2)])
+        );
+        assert_eq!(
+            hits(AttackStyle::Multi { targets: None }, 1, &candidates),
+            Hits::Projectiles(vec![Entity::new(1), Entity::new(2), Entity::new(0)])
+        );
+    }
+
+    #[test]
+    fn beam_hits_the_line() {
+        let candidates = vec![
+            ground(0, 2.0),
+            off_axis(1, 4.0, 0.3),
+            // Off the line
+            off_axis(2, 3.0, 2.0),
+            // Behind the tower
+            ground(3, -2.0),
+        ];
+        assert_eq!(
+            hits(AttackStyle::Beam, 0, &candidates),
+            Hits::Instant(vec![Entity::new(0), Entity::new(1)])
+        );
+    }
+
+    #[test]
+    fn beam_on_the_tower_hits_the_target() {
+        let candidates = vec![ground(0, 0.0), ground(1, 0.3), ground(2, 3.0)];
+        assert_eq!(
+            hits(AttackStyle::Beam, 0, &candidates),
+            Hits::Instant(vec![Entity::new(0)])
+        );
+    }
+
+    #[test]
+    fn chain_jumps_to_closest() {
+        let candidates = vec![
+            ground(0, 1.0),
+            ground(1, 4.0),
+            ground(2, 2.0),
+            // Too far from every other creep
+            ground(3, 10.0),
+        ];
+        let chain = AttackStyle::Chain {
+            jumps: 3,
+            range: Range(2.5),
+        };
+        assert_eq!(
+            hits(chain, 0, &candidates),
+            Hits::Instant(vec![Entity::new(0), Entity::new(2), Entity::new(1)])
+        );
+    }
+
+    #[test]
+    fn no_target_no_hits() {
+        assert!(hits(AttackStyle::Projectile, 0, &[]).is_empty());
+    }
+}
@@ -0,0 +1,191 @@
+use super::{
+    attack::AttackStyle,
+    special::{Special, SpecialType},
+    targeting::TargetingMode,
+    AttackSpeed, BaseAttackSpeed, BaseRange, Cooldown, Damage, Gem, GemQuality, GemType, Range,
+    Target, TargetFilter, Tower, TowerBundle,
+};
+use crate::{
+    abilities::{
+        aura::{Aura, Auras},
+        on_hit::OnHit,
+        OnHitAbilities,
+    },
+    creeps,
+    damage::DamageType,
+};
+use anyhow::bail;
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use strum::IntoEnumIterator;
+
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<TowerDefinitions>()
+            .init_asset_loader::<TowerDefinitionsLoader>()
+            .add_system(TowerDefinitions::sync);
+    }
+}
+
+/// Stats of every tower, loaded from a RON file with the `towers` extension
+#[derive(Clone, Deserialize, TypeUuid)]
+#[uuid = "d451cd2f-a3be-45e2-90a7-42bdb038ff14"]
+pub struct TowerDefinitions {
+    pub gems: HashMap<GemType, HashMap<GemQuality, TowerDefinition>>,
+    pub specials: HashMap<SpecialType, TowerDefinition>,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct TowerDefinition {
+    pub name: String,
+    pub damage: Damage,
+    #[serde(default)]
+    pub damage_type: DamageType,
+    pub speed: f32,
+    pub range: f32,
+    #[serde(default)]
+    pub abilities: Vec<OnHit>,
+    #[serde(default)]
+    pub auras: Vec<Aura>,
+    #[serde(default)]
+    pub targets: Option<creeps::Type>,
+    #[serde(default)]
+    pub targeting: TargetingMode,
+    #[serde(default)]
+    pub attack: AttackStyle,
+}
+
+impl TowerDefinition {
+    pub fn bundle(&self) -> TowerBundle {
+        TowerBundle {
+            name: Name::new(self.name.clone()),
+            damage: self.damage.clone(),
+            damage_type: self.damage_type,
+            base_speed: BaseAttackSpeed(self.speed),
+            speed: AttackSpeed(self.speed),
+            base_range: BaseRange(self.range),
+            range: Range(self.range),
+            cooldown: Cooldown::default(),
+            abilities: OnHitAbilities(self.abilities.clone()),
+            auras: Auras(self.auras.clone()),
+            target: Target::default(),
+            filter: TargetFilter(self.targets),
+            targeting: self.targeting,
+            attack: self.attack,
+        }
+    }
+}
+
+impl TowerDefinitions {
+    /// # Panics
+    ///
+    /// Will panic if the gem has no definition, which the loader rejects
+    pub fn gem(&self, gem: Gem) -> TowerBundle {
+        self.gems[&gem.r#type][&gem.quality].bundle()
+    }
+
+    /// # Panics
+    ///
+    /// Will panic if the special tower has no definition, which the loader rejects
+    pub fn special(&self, r#type: SpecialType) -> TowerBundle {
+        self.specials[&r#type].bundle()
+    }
+
+    /// Parses and validates the contents of a `towers` file
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the RON is malformed or describes an invalid tower
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        let definitions: Self = ron::de::from_bytes(bytes)?;
+        definitions.validate()?;
+        Ok(definitions)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        for r#type in GemType::iter() {
+            for quality in GemQuality::iter() {
+                if self
+                    .gems
+                    .get(&r#type)
+                    .and_then(|qualities| qualities.get(&quality))
+                    .is_none()
+                {
+                    bail!("Missing tower definition for {:?} {:?}", quality, r#type);
+                }
+            }
+        }
+        for r#type in SpecialType::iter() {
+            if !self.specials.contains_key(&r#type) {
+                bail!("Missing tower definition for {:?}", r#type);
+            }
+        }
+        Ok(())
+    }
+
+    /// Makes the most recently loaded definitions available as a resource, and applies them to every existing tower
+    /// when the file is changed while the game is running
+    pub fn sync(
+        mut commands: Commands,
+        mut er: EventReader<AssetEvent<TowerDefinitions>>,
+        assets: Res<Assets<TowerDefinitions>>,
+        gems: Query<(Entity, &Gem), With<Tower>>,
+        specials: Query<(Entity, &Special), With<Tower>>,
+    ) {
+        for event in er.iter() {
+            let (handle, modified) = match event {
+                AssetEvent::Created { handle } => (handle, false),
+                AssetEvent::Modified { handle } => (handle, true),
+                AssetEvent::Removed { .. } => continue,
+            };
+            let definitions = if let Some(definitions) = assets.get(handle) {
+                definitions
+            } else {
+                continue;
+            };
+
+            if modified {
+                info!("Reloading tower definitions");
+                for (entity, gem) in gems.iter() {
+                    commands.entity(entity).insert_bundle(definitions.gem(*gem));
+                }
+                for (entity, Special(r#type)) in specials.iter() {
+                    commands
+                        .entity(entity)
+                        .insert_bundle(definitions.special(*r#type));
+                }
+            }
+
+            commands.insert_resource(definitions.clone());
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct TowerDefinitionsLoader;
+
+impl AssetLoader for TowerDefinitionsLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let definitions = TowerDefinitions::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(definitions));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["towers"]
+    }
+}
@@ -0,0 +1,265 @@
+use super::{
+    definitions::TowerDefinitions,
+    special::{Special, SpecialType},
+    turn_into_rock, Gem, GemQuality, GemType, JustBuilt, Tower,
+};
+use crate::{grid::Grid, level_1::LevelState};
+use bevy::prelude::{self, *};
+use std::collections::BTreeMap;
+
+pub struct Plugin;
+
+impl prelude::Plugin for Plugin {
+    fn build(&self, app: &mut prelude::App) {
+        app.insert_resource(Recipes::default())
+            .add_event::<CombineRecipe>()
+            .add_system_set(SystemSet::on_update(LevelState::Choosing).with_system(combine_recipe));
+    }
+}
+
+pub type Ingredient = (GemType, GemQuality);
+
+/// Registry of special towers keyed on the multiset of gems needed to build them
+pub struct Recipes(BTreeMap<Vec<Ingredient>, SpecialType>);
+
+impl Default for Recipes {
+    fn default() -> Self {
+        use GemQuality::*;
+        use GemType::*;
+        let mut recipes = Self(BTreeMap::new());
+        recipes.add(
+            vec![(Topaz, Chipped), (Diamond, Chipped), (Sapphire, Chipped)],
+            SpecialType::Silver,
+        );
+        recipes.add(
+            vec![(Opal, Chipped), (Emerald, Chipped), (Aquamarine, Chipped)],
+            SpecialType::Malachite,
+        );
+        recipes.add(
+            vec![(Ruby, Chipped), (Ruby, Flawed), (Amethyst, Chipped)],
+            SpecialType::StarRuby,
+        );
+        recipes.add(
+            vec![(Emerald, Normal), (Opal, Normal), (Sapphire, Flawed)],
+            SpecialType::Jade,
+        );
+        recipes.add(
+            vec![(Diamond, Perfect), (Topaz, Normal), (Diamond, Normal)],
+            SpecialType::PinkDiamond,
+        );
+        recipes.add(
+            vec![(Ruby, Perfect), (Aquamarine, Flawless), (Amethyst, Normal)],
+            SpecialType::Bloodstone,
+        );
+        recipes
+    }
+}
+
+impl Recipes {
+    pub fn add(&mut self, mut ingredients: Vec<Ingredient>, result: SpecialType) {
+        ingredients.sort_unstable();
+        self.0.insert(ingredients, result);
+    }
+
+    /// Finds a recipe that uses `required` and can be built entirely from `available`, returning its result and ingredients
+    pub fn find(
+        &self,
+        required: Ingredient,
+        available: &[Ingredient],
+    ) -> Option<(SpecialType, &[Ingredient])> {
+        self.0
+            .iter()
+            .find(|(ingredients, _)| {
+                ingredients.contains(&required) && is_subset(ingredients, available)
+            })
+            .map(|(ingredients, result)| (*result, ingredients.as_slice()))
+    }
+}
+
+fn is_subset(ingredients: &[Ingredient], available: &[Ingredient]) -> bool {
+    let mut remaining = available.to_vec();
+    ingredients.iter().all(|ingredient| {
+        if let Some(index) = remaining.iter().position(|gem| gem == ingredient) {
+            remaining.swap_remove(index);
+            true
+        } else {
+            false
+        }
+    })
+}
+
+pub struct CombineRecipe {
+    pub pos: (i32, i32),
+}
+
+/// Turns the chosen gem into the special tower of a recipe it is part of, using gems built this round first and
+/// already placed towers after that. Every other gem used or built this round turns into a rock.
+fn combine_recipe(
+    mut commands: Commands,
+    mut er: EventReader<CombineRecipe>,
+    grid: Res<Grid>,
+    recipes: Res<Recipes>,
+    definitions: Res<TowerDefinitions>,
+    just_built: Query<(Entity, &Gem), With<JustBuilt>>,
+    placed: Query<(Entity, &Gem), (With<Tower>, Without<JustBuilt>)>,
+) {
+    for CombineRecipe { pos } in er.iter() {
+        let chosen_entity = if let Some(entity) = grid.get(*pos) {
+            entity
+        } else {
+            continue;
+        };
+
+        let chosen = if let Ok((_, gem)) = just_built.get(chosen_entity) {
+            *gem
+        } else {
+            continue;
+        };
+
+        // The chosen gem goes first so it is always the one consuming its own ingredient slot
+        let mut candidates = vec![(chosen_entity, chosen)];
+        candidates.extend(
+            just_built
+                .iter()
+                .filter(|(entity, _)| *entity != chosen_entity)
+                .map(|(entity, gem)| (entity, *gem)),
+        );
+        candidates.extend(placed.iter().map(|(entity, gem)| (entity, *gem)));
+
+        let available: Vec<Ingredient> = candidates
+            .iter()
+            .map(|(_, gem)| (gem.r#type, gem.quality))
+            .collect();
+
+        let (result, ingredients) =
+            if let Some(recipe) = recipes.find((chosen.r#type, chosen.quality), &available) {
+                recipe
+            } else {
+                info!(
+                    "No recipe uses {:?} {:?} with the available gems",
+                    chosen.quality, chosen.r#type
+                );
+                continue;
+            };
+
+        let mut consumed = vec![];
+        for ingredient in ingredients {
+            if let Some(index) = candidates
+                .iter()
+                .position(|(_, gem)| (gem.r#type, gem.quality) == *ingredient)
+            {
+                consumed.push(candidates.remove(index).0);
+            }
+        }
+
+        commands
+            .entity(chosen_entity)
+            .remove::<Gem>()
+            .insert_bundle(definitions.special(result))
+            .insert(Special(result));
+
+        // Gems built this round are turned into rocks below regardless of being used
+        for entity in consumed {
+            if placed.get(entity).is_ok() {
+                turn_into_rock(&mut commands, entity);
+            }
+        }
+        for (entity, _) in just_built.iter() {
+            if entity != chosen_entity {
+                turn_into_rock(&mut commands, entity);
+            }
+            commands.entity(entity).remove::<JustBuilt>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{simulation::Simulation, towers::Rock};
+    use GemQuality::*;
+    use GemType::*;
+
+    #[test]
+    fn duplicate_ingredients_need_as_many_gems() {
+        let mut recipes = Recipes(BTreeMap::new());
+        recipes.add(
+            vec![(Ruby, Chipped), (Ruby, Chipped), (Topaz, Chipped)],
+            SpecialType::StarRuby,
+        );
+        let available = [(Ruby, Chipped), (Topaz, Chipped)];
+        assert!(recipes.find((Ruby, Chipped), &available).is_none());
+
+        let available = [(Ruby, Chipped), (Topaz, Chipped), (Ruby, Chipped)];
+        let (result, ingredients) = recipes.find((Ruby, Chipped), &available).unwrap();
+        assert_eq!(result, SpecialType::StarRuby);
+        assert_eq!(ingredients.len(), 3);
+    }
+
+    #[test]
+    fn required_gem_must_be_an_ingredient() {
+        let recipes = Recipes::default();
+        let available = [
+            (Topaz, Chipped),
+            (Diamond, Chipped),
+            (Sapphire, Chipped),
+            (Ruby, Chipped),
+        ];
+        assert!(recipes.find((Ruby, Chipped), &available).is_none());
+        assert_eq!(
+            recipes.find((Topaz, Chipped), &available).unwrap().0,
+            SpecialType::Silver
+        );
+    }
+
+    #[test]
+    fn combines_built_and_placed_gems() {
+        let mut simulation = Simulation::load(
+            "assets/maps/level_1.map",
+            "assets/gems.towers",
+            "assets/level_1.waves",
+        )
+        .unwrap();
+        let mut place =
+            |pos, r#type, quality| simulation.place_gem(pos, Gem { quality, r#type }).unwrap();
+        let chosen = place((-10, 4), Ruby, Chipped);
+        let unused_built = place((-14, 4), Topaz, Chipped);
+        let placed = [
+            place((-6, 4), Ruby, Flawed),
+            place((-2, 4), Amethyst, Chipped),
+        ];
+        let unused_placed = place((-18, 4), Sapphire, Chipped);
+
+        let built = [(chosen, Ruby, Chipped), (unused_built, Topaz, Chipped)];
+        let world = simulation.world_mut();
+        for (entity, _, _) in &built {
+            world.entity_mut(*entity).insert(JustBuilt);
+        }
+        world
+            .get_resource_mut::<State<LevelState>>()
+            .unwrap()
+            .set(LevelState::Choosing)
+            .unwrap();
+        simulation.update();
+        // Revealing rolled new gems, put back the ones the test needs
+        for (entity, r#type, quality) in built {
+            simulation
+                .world_mut()
+                .entity_mut(entity)
+                .insert(Gem { quality, r#type });
+        }
+
+        simulation.send(CombineRecipe { pos: (-10, 4) });
+        simulation.update();
+        let world = simulation.world();
+        assert_eq!(
+            world.get::<Special>(chosen),
+            Some(&Special(SpecialType::StarRuby))
+        );
+        for entity in placed.iter().chain(&[unused_built]) {
+            assert!(world.get::<Rock>(*entity).is_some());
+        }
+        assert!(world.get::<Rock>(unused_placed).is_none());
+        assert!(world.get::<Gem>(unused_placed).is_some());
+    }
+}
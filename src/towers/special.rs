@@ -0,0 +1,30 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use strum_macros::EnumIter;
+
+/// Towers that cannot be rolled, only built from a recipe of specific gems
+#[derive(Clone, Copy, Debug, Deserialize, EnumIter, PartialEq, Eq, Hash, Serialize)]
+pub enum SpecialType {
+    Silver,
+    Malachite,
+    StarRuby,
+    Jade,
+    PinkDiamond,
+    Bloodstone,
+}
+
+impl SpecialType {
+    pub fn color(self) -> Color {
+        match self {
+            SpecialType::Silver => Color::SILVER,
+            SpecialType::Malachite => Color::SEA_GREEN,
+            SpecialType::StarRuby => Color::CRIMSON,
+            SpecialType::Jade => Color::LIME_GREEN,
+            SpecialType::PinkDiamond => Color::PINK,
+            SpecialType::Bloodstone => Color::MAROON,
+        }
+    }
+}
+
+#[derive(Clone, Component, Copy, Debug, PartialEq, Eq)]
+pub struct Special(pub SpecialType);
@@ -0,0 +1,265 @@
+use super::{Range, Target, TargetFilter, Tower};
+use crate::{
+    creeps::{self, Life, Movement},
+    grid::Grid,
+};
+use bevy::{
+    math::Vec3Swizzles,
+    prelude::{self, *},
+};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+pub struct Plugin;
+
+impl prelude::Plugin for Plugin {
+    fn build(&self, app: &mut prelude::App) {
+        app.add_event::<SetTargeting>()
+            .add_system(set_targeting)
+            .add_system(pick_target);
+    }
+}
+
+/// How a tower picks between the creeps within its range, defaults are given per tower in the definitions
+#[derive(Clone, Component, Copy, Debug, Deserialize, EnumIter, PartialEq, Eq, Serialize)]
+pub enum TargetingMode {
+    /// Furthest along its route
+    First,
+    /// Least far along its route
+    Last,
+    /// Most life remaining
+    Strongest,
+    /// Least life remaining
+    Weakest,
+    Closest,
+    /// Flying creeps before ground creeps, then the first
+    FlyingFirst,
+}
+
+impl Default for TargetingMode {
+    fn default() -> Self {
+        TargetingMode::Closest
+    }
+}
+
+impl TargetingMode {
+    /// The mode after this one, wrapping around
+    pub fn next(self) -> Self {
+        let modes = TargetingMode::iter().collect::<Vec<_>>();
+        let index = modes.iter().position(|mode| *mode == self).unwrap_or(0);
+        modes[(index + 1) % modes.len()]
+    }
+
+    /// Picks a target within range from the candidates, only creeps allowed by the filter are considered
+    fn pick(
+        self,
+        tower: Vec3,
+        range: Range,
+        filter: Option<creeps::Type>,
+        candidates: &[Candidate],
+    ) -> Option<Entity> {
+        reachable(tower, range, filter, candidates)
+            .min_by(|a, b| self.compare(tower, a, b))
+            .map(|candidate| candidate.entity)
+    }
+
+    /// Every candidate `pick` would consider, the preferred first
+    pub(super) fn sort<'a>(
+        self,
+        tower: Vec3,
+        range: Range,
+        filter: Option<creeps::Type>,
+        candidates: &'a [Candidate],
+    ) -> Vec<&'a Candidate> {
+        let mut reachable = reachable(tower, range, filter, candidates).collect::<Vec<_>>();
+        reachable.sort_by(|a, b| self.compare(tower, a, b));
+        reachable
+    }
+
+    /// Orders candidates so the preferred one comes first
+    fn compare(self, tower: Vec3, a: &Candidate, b: &Candidate) -> Ordering {
+        let distance = |candidate: &Candidate| tower.xz().distance_squared(candidate.position.xz());
+        match self {
+            TargetingMode::First => compare_f32(a.remaining, b.remaining),
+            TargetingMode::Last => compare_f32(b.remaining, a.remaining),
+            TargetingMode::Strongest => b.life.cmp(&a.life),
+            TargetingMode::Weakest => a.life.cmp(&b.life),
+            TargetingMode::Closest => compare_f32(distance(a), distance(b)),
+            TargetingMode::FlyingFirst => {
+                let flying = |candidate: &Candidate| match candidate.r#type {
+                    creeps::Type::Flying => 0,
+                    creeps::Type::Ground => 1,
+                };
+                flying(a)
+                    .cmp(&flying(b))
+                    .then_with(|| TargetingMode::First.compare(tower, a, b))
+            }
+        }
+    }
+}
+
+/// Candidates within range allowed by the filter
+pub(super) fn reachable(
+    tower: Vec3,
+    Range(range): Range,
+    filter: Option<creeps::Type>,
+    candidates: &[Candidate],
+) -> impl Iterator<Item = &Candidate> {
+    candidates.iter().filter(move |candidate| {
+        // Towers reach flying creeps as long as they are within range horizontally
+        tower.xz().distance_squared(candidate.position.xz()) <= range.powf(2.0)
+            && filter.map_or(true, |filter| {
+                matches!(
+                    (filter, candidate.r#type),
+                    (creeps::Type::Ground, creeps::Type::Ground)
+                        | (creeps::Type::Flying, creeps::Type::Flying)
+                )
+            })
+    })
+}
+
+fn compare_f32(a: f32, b: f32) -> Ordering {
+    a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+}
+
+/// Changes the targeting mode of the tower at the given position
+pub struct SetTargeting {
+    pub pos: (i32, i32),
+    pub mode: TargetingMode,
+}
+
+fn set_targeting(
+    mut commands: Commands,
+    mut er: EventReader<SetTargeting>,
+    grid: Res<Grid>,
+    towers: Query<(), With<Tower>>,
+) {
+    for SetTargeting { pos, mode } in er.iter() {
+        match grid.get(*pos).filter(|entity| towers.get(*entity).is_ok()) {
+            Some(entity) => {
+                commands.entity(entity).insert(*mode);
+            }
+            None => info!("No tower at {};{} to change the targeting of", pos.0, pos.1),
+        }
+    }
+}
+
+/// Creep a tower could target, with everything the targeting modes compare
+pub(super) struct Candidate {
+    pub entity: Entity,
+    pub position: Vec3,
+    pub r#type: creeps::Type,
+    pub life: u64,
+    /// Distance left to walk or fly along the route
+    pub remaining: f32,
+}
+
+impl Candidate {
+    /// Every creep alive this frame
+    pub(super) fn all(
+        creeps: &Query<(Entity, &GlobalTransform, &creeps::Type, &Life, &Movement)>,
+    ) -> Vec<Candidate> {
+        creeps
+            .iter()
+            .map(
+                |(entity, transform, r#type, Life(life), movement)| Candidate {
+                    entity,
+                    position: transform.translation,
+                    r#type: *r#type,
+                    life: *life,
+                    remaining: movement.remaining(transform.translation),
+                },
+            )
+            .collect()
+    }
+}
+
+/// Targets are picked again every frame, so towers switch as soon as a better creep comes into range
+fn pick_target(
+    mut towers: Query<
+        (
+            &GlobalTransform,
+            &Range,
+            &TargetFilter,
+            &TargetingMode,
+            &mut Target,
+        ),
+        Without<creeps::Type>,
+    >,
+    creeps: Query<(Entity, &GlobalTransform, &creeps::Type, &Life, &Movement)>,
+) {
+    let candidates = Candidate::all(&creeps);
+
+    for (tower_pos, range, TargetFilter(filter), mode, mut target) in towers.iter_mut() {
+        let picked = mode.pick(tower_pos.translation, *range, *filter, &candidates);
+        if target.0 != picked {
+            target.0 = picked;
+        }
+    }
+}
+
+/// Candidate at `x` on the x axis, shared by the tests of the towers modules
+#[cfg(test)]
+pub(super) fn candidate(
+    id: u32,
+    x: f32,
+    r#type: creeps::Type,
+    life: u64,
+    remaining: f32,
+) -> Candidate {
+    Candidate {
+        entity: Entity::new(id),
+        position: Vec3::new(x, 0.0, 0.0),
+        r#type,
+        life,
+        remaining,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn candidates() -> Vec<Candidate> {
+        vec![
+            candidate(0, 1.0, creeps::Type::Ground, 50, 30.0),
+            candidate(1, 2.0, creeps::Type::Ground, 10, 10.0),
+            candidate(2, 3.0, creeps::Type::Flying, 90, 20.0),
+            // Out of range
+            candidate(3, 20.0, creeps::Type::Ground, 5, 1.0),
+        ]
+    }
+
+    fn pick(mode: TargetingMode, filter: Option<creeps::Type>) -> Option<Entity> {
+        mode.pick(Vec3::ZERO, Range(5.0), filter, &candidates())
+    }
+
+    #[test]
+    fn modes() {
+        assert_eq!(pick(TargetingMode::First, None), Some(Entity::new(1)));
+        assert_eq!(pick(TargetingMode::Last, None), Some(Entity::new(0)));
+        assert_eq!(pick(TargetingMode::Strongest, None), Some(Entity::new(2)));
+        assert_eq!(pick(TargetingMode::Weakest, None), Some(Entity::new(1)));
+        assert_eq!(pick(TargetingMode::Closest, None), Some(Entity::new(0)));
+        assert_eq!(pick(TargetingMode::FlyingFirst, None), Some(Entity::new(2)));
+    }
+
+    #[test]
+    fn filter_skips_creeps() {
+        assert_eq!(
+            pick(TargetingMode::Strongest, Some(creeps::Type::Ground)),
+            Some(Entity::new(0))
+        );
+        assert_eq!(
+            pick(TargetingMode::FlyingFirst, Some(creeps::Type::Ground)),
+            Some(Entity::new(1))
+        );
+    }
+
+    #[test]
+    fn next_wraps_around() {
+        assert_eq!(TargetingMode::FlyingFirst.next(), TargetingMode::First);
+    }
+}
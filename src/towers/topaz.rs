@@ -1,106 +0,0 @@
-use super::{
-    cooldown_is_done, launch_projectile, AttackSpeed, Cooldown, Gem, GemQuality, GemType, Range,
-    TowerBundle, BASE_TOWER_SPEED,
-};
-use crate::{
-    abilities::{aura::Auras, OnHitAbilities},
-    creeps,
-    level_1::LevelState,
-    towers::{get_all_creeps_within_range, Damage, Target},
-};
-use bevy::prelude::{self, *};
-
-pub struct Plugin;
-
-impl prelude::Plugin for Plugin {
-    fn build(&self, app: &mut prelude::App) {
-        app.add_system_set(SystemSet::on_update(LevelState::Spawning).with_system(attack));
-    }
-}
-
-fn attack(
-    mut commands: Commands,
-    time: Res<Time>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut gems: Query<(
-        Entity,
-        &GlobalTransform,
-        &Gem,
-        &AttackSpeed,
-        &Range,
-        &mut Cooldown,
-    )>,
-    creeps: Query<(Entity, &GlobalTransform, &creeps::Type)>,
-) {
-    for (gem_entity, gem_position, gem, AttackSpeed(speed), range, mut cooldown) in gems.iter_mut()
-    {
-        if !matches!(gem.r#type, GemType::Topaz) {
-            continue;
-        }
-
-        if !cooldown_is_done(&mut *cooldown, *speed, &time) {
-            continue;
-        }
-
-        for creep in get_all_creeps_within_range(&creeps, gem_position, *range, None) {
-            cooldown.0.reset();
-
-            launch_projectile(&mut commands, &mut meshes, gem_position, gem_entity, creep);
-        }
-    }
-}
-
-pub fn tower(quality: GemQuality) -> TowerBundle {
-    match quality {
-        GemQuality::Chipped => TowerBundle {
-            name: Name::new("Chipped topaz"),
-            damage: Damage::Fixed(4),
-            speed: AttackSpeed(BASE_TOWER_SPEED - 0.2),
-            range: Range(5.0),
-            cooldown: Cooldown(Timer::from_seconds(1.0, true)),
-            abilities: OnHitAbilities(vec![]),
-            auras: Auras(vec![]),
-            target: Target::default(),
-        },
-        GemQuality::Flawed => TowerBundle {
-            name: Name::new("Flawed topaz"),
-            damage: Damage::Fixed(8),
-            speed: AttackSpeed(BASE_TOWER_SPEED),
-            range: Range(5.0),
-            cooldown: Cooldown(Timer::from_seconds(1.0, true)),
-            abilities: OnHitAbilities(vec![]),
-            auras: Auras(vec![]),
-            target: Target::default(),
-        },
-        GemQuality::Normal => TowerBundle {
-            name: Name::new("Topaz"),
-            damage: Damage::Fixed(14),
-            speed: AttackSpeed(BASE_TOWER_SPEED),
-            range: Range(5.0),
-            cooldown: Cooldown(Timer::from_seconds(1.0, true)),
-            abilities: OnHitAbilities(vec![]),
-            auras: Auras(vec![]),
-            target: Target::default(),
-        },
-        GemQuality::Flawless => TowerBundle {
-            name: Name::new("Flawless topaz"),
-            damage: Damage::Fixed(25),
-            speed: AttackSpeed(BASE_TOWER_SPEED),
-            range: Range(5.0),
-            cooldown: Cooldown(Timer::from_seconds(1.0, true)),
-            abilities: OnHitAbilities(vec![]),
-            auras: Auras(vec![]),
-            target: Target::default(),
-        },
-        GemQuality::Perfect => TowerBundle {
-            name: Name::new("Perfect topaz"),
-            damage: Damage::Fixed(75),
-            speed: AttackSpeed(BASE_TOWER_SPEED),
-            range: Range(5.0),
-            cooldown: Cooldown(Timer::from_seconds(1.0, true)),
-            abilities: OnHitAbilities(vec![]),
-            auras: Auras(vec![]),
-            target: Target::default(),
-        },
-    }
-}
@@ -0,0 +1,187 @@
+use crate::{
+    buffs::Debuffs,
+    creeps::{self, Armor, BaseArmor, Bounty, CreepBundle, Leak, Life, Movement, Speed},
+    damage::ArmorType,
+    effects::Effects,
+};
+use anyhow::bail;
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::{self, *},
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+
+pub struct Plugin;
+
+impl prelude::Plugin for Plugin {
+    fn build(&self, app: &mut prelude::App) {
+        app.add_asset::<WaveDefinitions>()
+            .init_asset_loader::<WaveDefinitionsLoader>()
+            .add_system(WaveDefinitions::sync);
+    }
+}
+
+/// Creep waves in level order, loaded from a RON file with the `waves` extension
+#[derive(Clone, Deserialize, TypeUuid)]
+#[uuid = "61efce91-b278-4e88-90b6-8db76bc4c42f"]
+pub struct WaveDefinitions(pub Vec<WaveDefinition>);
+
+#[derive(Clone, Deserialize)]
+pub struct WaveDefinition {
+    pub count: u32,
+    /// Seconds between each creep spawning
+    pub interval: f32,
+    pub life: u64,
+    pub speed: f32,
+    #[serde(default = "default_min_speed")]
+    pub min_speed: f32,
+    pub r#type: creeps::Type,
+    pub bounty: u32,
+    pub leak: u32,
+    #[serde(default)]
+    pub armor: u32,
+    #[serde(default)]
+    pub armor_type: ArmorType,
+    #[serde(default)]
+    pub traits: Vec<Trait>,
+}
+
+fn default_min_speed() -> f32 {
+    0.2
+}
+
+#[derive(Clone, Copy, Deserialize, PartialEq, Eq)]
+pub enum Trait {
+    /// Cannot be slowed
+    Immune,
+}
+
+impl WaveDefinitions {
+    /// Returns the wave for the given level, levels past the last wave repeat it
+    ///
+    /// # Panics
+    ///
+    /// Will panic if there are no waves, which the loader rejects
+    pub fn get(&self, level: u32) -> &WaveDefinition {
+        let index = (level.max(1) - 1) as usize;
+        self.0.get(index).unwrap_or_else(|| self.0.last().unwrap())
+    }
+
+    /// Parses and validates the contents of a `waves` file
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the RON is malformed or describes an invalid wave
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        let waves: Self = ron::de::from_bytes(bytes)?;
+        waves.validate()?;
+        Ok(waves)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.0.is_empty() {
+            bail!("No waves defined");
+        }
+        for (index, wave) in self.0.iter().enumerate() {
+            let level = index + 1;
+            if wave.count == 0 {
+                bail!("Wave {} spawns no creeps", level);
+            }
+            if !wave.interval.is_finite() || wave.interval <= 0.0 {
+                bail!(
+                    "Wave {} has spawn interval {}, it must be positive",
+                    level,
+                    wave.interval
+                );
+            }
+            if wave.life == 0 {
+                bail!("Wave {} has creeps without life", level);
+            }
+            if !wave.speed.is_finite() || wave.speed <= 0.0 {
+                bail!(
+                    "Wave {} has speed {}, it must be positive",
+                    level,
+                    wave.speed
+                );
+            }
+            if wave.min_speed < 0.0 || wave.min_speed > wave.speed {
+                bail!(
+                    "Wave {} has minimum speed {}, it must be between 0 and its speed {}",
+                    level,
+                    wave.min_speed,
+                    wave.speed
+                );
+            }
+        }
+        Ok(())
+    }
+
+    pub fn sync(
+        mut commands: Commands,
+        mut er: EventReader<AssetEvent<WaveDefinitions>>,
+        assets: Res<Assets<WaveDefinitions>>,
+    ) {
+        for event in er.iter() {
+            match event {
+                AssetEvent::Created { handle } | AssetEvent::Modified { handle } => {
+                    if let Some(waves) = assets.get(handle) {
+                        commands.insert_resource(waves.clone());
+                    }
+                }
+                AssetEvent::Removed { .. } => {}
+            }
+        }
+    }
+}
+
+impl WaveDefinition {
+    pub fn creep(&self, route: Vec<(i32, i32)>) -> CreepBundle {
+        CreepBundle {
+            life: Life(self.life),
+            movement: Movement {
+                route,
+                destination: 0,
+            },
+            speed: Speed {
+                base: self.speed,
+                min: self.min_speed,
+                modifier: 0,
+            },
+            r#type: self.r#type,
+            leak: Leak(self.leak),
+            bounty: Bounty(self.bounty),
+            base_armor: BaseArmor(self.armor),
+            armor: Armor(self.armor),
+            armor_type: self.armor_type,
+            effects: Effects::default(),
+            debuffs: Debuffs::default(),
+        }
+    }
+
+    pub fn has_trait(&self, r#trait: Trait) -> bool {
+        self.traits.contains(&r#trait)
+    }
+}
+
+#[derive(Default)]
+pub struct WaveDefinitionsLoader;
+
+impl AssetLoader for WaveDefinitionsLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let waves = WaveDefinitions::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(waves));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["waves"]
+    }
+}